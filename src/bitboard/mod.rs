@@ -1,13 +1,17 @@
 pub mod sizedint;
 
+use std::fmt::Debug;
+
 use num::{PrimInt, Unsigned};
 use num_traits::{WrappingMul, WrappingSub};
 
+/// `Send + Sync` are required so that a `Game` (and its lookup tables) can be shared across threads,
+/// and `'static` so that boxed pieces generic over `T` don't need lifetimes.
 pub trait BitInt:
-    PrimInt + Unsigned + WrappingMul + WrappingSub {}
+    PrimInt + Unsigned + WrappingMul + WrappingSub + Debug + Send + Sync + 'static {}
 
 impl<T> BitInt for T where
-    T: PrimInt + Unsigned + WrappingMul + WrappingSub
+    T: PrimInt + Unsigned + WrappingMul + WrappingSub + Debug + Send + Sync + 'static
 {}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -26,6 +30,8 @@ pub struct Edges<T: BitInt> {
     pub top: BitBoard<T>,
     pub bottom: BitBoard<T>,
     pub all: BitBoard<T>,
+    /// The width of the board, which is how far `try_up` and `try_down` shift by.
+    pub cols: u16,
 }
 
 pub struct BitPositions<T: BitInt>(T);
@@ -93,10 +99,12 @@ impl<T: BitInt> BitBoard<T> {
         BitBoard(self.0 >> tiles)
     }
 
+    /// Moves every square up `tiles` rows of an 8 column board. Use `try_up` for other widths.
     pub fn up(self, tiles: usize) -> BitBoard<T> {
         BitBoard(self.0 << (8 * tiles))
     }
 
+    /// Moves every square down `tiles` rows of an 8 column board. Use `try_down` for other widths.
     pub fn down(self, tiles: usize) -> BitBoard<T> {
         BitBoard(self.0 >> (8 * tiles))
     }
@@ -117,14 +125,14 @@ impl<T: BitInt> BitBoard<T> {
     
     pub fn try_up(mut self, edges: &Edges<T>, tiles: usize) -> BitBoard<T> {
         for _ in 0..tiles {
-            self = self.and_not(edges.top).up(1);
+            self = BitBoard(self.and_not(edges.top).0 << (edges.cols as usize));
         }
         self
     }
     
     pub fn try_down(mut self, edges: &Edges<T>, tiles: usize) -> BitBoard<T> {
         for _ in 0..tiles {
-            self = self.and_not(edges.bottom).down(1);
+            self = BitBoard(self.and_not(edges.bottom).0 >> (edges.cols as usize));
         }
         self
     }
@@ -225,6 +233,7 @@ impl<T: BitInt> BitBoard<T> {
             top,
             bottom,
             all,
+            cols: bounds.cols,
         }
     }
 
@@ -282,6 +291,16 @@ impl<T: BitInt> BitBoard<T> {
         Self::index(index)
     }
 
+    /// Mirrors the board across its horizontal middle, swapping the first and last rows.
+    pub fn flip_vertical(self, bounds: Bounds) -> BitBoard<T> {
+        let mut out = BitBoard::default();
+        for index in self.iter() {
+            let (row, col) = (index as u16 / bounds.cols, index as u16 % bounds.cols);
+            out = out.or(Self::index((bounds.rows - 1 - row) * bounds.cols + col));
+        }
+        out
+    }
+
     pub fn iter(self) -> BitPositions<T> {
         BitPositions::new(self.0)
     }
@@ -0,0 +1,93 @@
+use std::marker::PhantomData;
+
+use crate::{bitboard::{BitBoard, BitInt}, game::{drops::DropRules, Game, GameTemplate}};
+
+use super::{Chess, ChessMoves, BISHOP, KNIGHT, PAWN, QUEEN, ROOK};
+
+pub const CRAZYHOUSE_SUITE: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1;20;400;8902;197281;4888832
+2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1;301;75353
+2k5/8/8/8/8/8/8/4K3[Qn] w - - 0 1;67;3083;88634
+r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[] b KQkq - 0 1;42;1347;58057
+4k3/1Q~6/8/8/4b3/8/Kpp5/8[] b - - 0 1;20;360;5445";
+
+/// Chess where captured pieces go into the capturer's pocket, and can be dropped back onto the board instead of moving.
+///
+/// Pawns can't be dropped on either back rank, and promoted pieces (marked with `~` in FENs) go back into the pocket as pawns.
+pub struct Crazyhouse<Moves> {
+    _phantom: PhantomData<Moves>
+}
+
+impl<Moves: ChessMoves + Send + Sync + 'static> GameTemplate for Crazyhouse<Moves> {
+    fn create<T : BitInt, const N: usize>() -> Game<T, N> {
+        let mut game = Chess::<Moves>::create::<T, N>();
+        let bounds = game.bounds;
+
+        let pawn_squares = BitBoard::edges_left(bounds, bounds.cols)
+            .and_not(BitBoard::edges_up(bounds, 1))
+            .and_not(BitBoard::edges_down(bounds, 1));
+
+        game.default_pos = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1".to_string();
+        game.drops = DropRules::new(bounds)
+            .drop_on(PAWN, [ pawn_squares, pawn_squares ])
+            .unmoved(PAWN, 2)
+            .drop(KNIGHT, 0)
+            .drop(BISHOP, 0)
+            .drop(ROOK, 0)
+            .drop(QUEEN, 0);
+
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{MagicMoves, SliderMoves, PAWN, QUEEN}, game::{suite::test_suite, GameTemplate, Team}};
+
+    use super::{Crazyhouse, CRAZYHOUSE_SUITE};
+
+    #[test]
+    fn crazyhouse_suite() {
+        let crazyhouse = Crazyhouse::<SliderMoves>::create::<u64, 6>();
+        test_suite(CRAZYHOUSE_SUITE, &crazyhouse);
+    }
+
+    #[test]
+    fn promoted_pawns_revert() {
+        let crazyhouse = Crazyhouse::<MagicMoves>::create::<u64, 6>();
+
+        // The queen was a pawn, so taking it only puts a pawn into the pocket.
+        let mut board = crazyhouse.load("4k3/8/8/3q~4/4P3/8/8/4K3[Q] w - - 0 1");
+        board.play_action("e4d5");
+        assert_eq!(board.state.pockets[Team::White.index()][PAWN], 1);
+        assert_eq!(board.state.pockets[Team::White.index()][QUEEN], 1);
+        assert_eq!(crazyhouse.rules.save(&mut board), "4k3/8/8/3P4/8/8/8/4K3[PQ] b - - 0 1");
+
+        // Promoting marks the new piece, and dropping from the pocket doesn't.
+        let mut board = crazyhouse.load("8/P3k3/8/8/8/8/8/4K3[Q] w - - 0 1");
+        board.play_action("a7a8q");
+        board.play_action("e7e6");
+        board.play_action("Q@d5");
+        assert_eq!(crazyhouse.rules.save(&mut board), "Q~7/8/4k3/3Q4/8/8/8/4K3[] b - - 2 2");
+    }
+
+    #[test]
+    fn crazyhouse_drops() {
+        let crazyhouse = Crazyhouse::<SliderMoves>::create::<u64, 6>();
+
+        // Dropping a piece can block a check, and drops are written like `N@d2`.
+        let mut board = crazyhouse.load("4k3/8/8/8/1b6/8/8/4K3[N] w - - 0 1");
+        let mut moves: Vec<String> = board.legals().into_iter()
+            .filter(|action| action.is_drop())
+            .map(|action| board.display_uci_action(action))
+            .collect();
+        moves.sort();
+        assert_eq!(moves, vec![ "N@c3", "N@d2" ]);
+
+        // A rook dropped into the corner can't castle.
+        let mut board = crazyhouse.load("4k3/8/8/8/8/8/8/4K3[R] w - - 0 1");
+        board.play_action("R@h1");
+        board.play_null();
+        let moves: Vec<String> = board.legals().into_iter().map(|action| board.display_uci_action(action)).collect();
+        assert!(!moves.contains(&"e1g1".to_string()));
+    }
+}
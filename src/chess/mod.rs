@@ -4,15 +4,16 @@ use std::marker::PhantomData;
 
 use rustc_hash::FxHashMap as HashMap;
 
-use pieces::{leapers::{king::{make_castling_move, KingMoves}, knight::KnightMoves, leaper::Leaper}, pawn::{make_en_passant_move, make_promotion_move, Pawn}, sliders::{bishop::BishopMoves, magics::Magic, queen::QueenMoves, rook::RookMoves, slider::Slider}};
+use pieces::{leapers::{king::King, knight::KnightMoves, leaper::Leaper}, pawn::Pawn, sliders::{bishop::BishopMoves, magics::{Magic, MagicQueen}, queen::QueenMoves, rook::RookMoves, slider::Slider}};
 
-use crate::{bitboard::{BitBoard, BitInt, Bounds}, chess::pieces::leapers::king::add_castling_actions, game::{action::{index_to_square, make_chess_move, square_to_index, Action, ActionRecord}, zobrist::ZobristTable, Board, Game, GameRules, GameState, GameTemplate, Team}};
+use crate::{bitboard::{BitBoard, BitInt, Bounds}, game::{action::{index_to_square, square_to_index, Action, ActionRecord}, piece::Piece, drops::DropRules, promotion::{is_promotion, PromotionRules}, royalty::Royalty, zobrist::ZobristTable, Board, Game, GameRules, GameState, GameTemplate, LookupSlots, Team}};
 
+pub mod crazyhouse;
 pub mod pieces;
 pub mod suite;
 mod test_positions;
 
-// For other variants, these constants will need to be redefined. Oops!
+// The indexes `Chess` registers its pieces at. Pieces from other games should be found by name with `find_piece`.
 
 pub const PAWN: usize = 0;
 pub const KNIGHT: usize = 1;
@@ -56,25 +57,34 @@ fn extract_castling_rights<T : BitInt, const N: usize>(board: &Board<T, N>) -> C
     let left_side = BitBoard::edges_left(board.game.bounds, board.game.bounds.cols / 2);
     let right_side = BitBoard::edges_right(board.game.bounds, board.game.bounds.cols / 2);
 
-    let unmoved_rooks = board.state.first_move.and(board.state.pieces[ROOK]);
+    let Some(castling) = board.game.castling else {
+        return CastlingRights { white_king_side: false, white_queen_side: false, black_king_side: false, black_queen_side: false };
+    };
+
+    let unmoved_rooks = board.state.first_move.and(board.state.pieces[castling.rook]);
+    let unmoved_kings = board.state.first_move.and(board.state.pieces[castling.king]);
+
+    // Castling needs both the king and the rook to be unmoved.
+    let white_king = unmoved_kings.and(board.state.white).set();
+    let black_king = unmoved_kings.and(board.state.black).set();
 
     CastlingRights {
-        white_king_side: unmoved_rooks
+        white_king_side: white_king && unmoved_rooks
             .and(board.state.white)
             .and(right_side)
             .set(),
 
-        white_queen_side: unmoved_rooks
+        white_queen_side: white_king && unmoved_rooks
             .and(board.state.white)
             .and(left_side)
             .set(),
 
-        black_king_side: unmoved_rooks
+        black_king_side: black_king && unmoved_rooks
             .and(board.state.black)
             .and(right_side)
             .set(),
 
-        black_queen_side: unmoved_rooks
+        black_queen_side: black_king && unmoved_rooks
             .and(board.state.black)
             .and(left_side)
             .set(),
@@ -82,79 +92,53 @@ fn extract_castling_rights<T : BitInt, const N: usize>(board: &Board<T, N>) -> C
 }
 
 pub trait ChessMoves {
-    fn actions<T : BitInt, const N: usize>(board: &mut Board<T, N>) -> Vec<Action>;
-    fn attacks<T : BitInt, const N: usize>(board: &mut Board<T, N>, mask: BitBoard<T>) -> bool;
-    fn process<T : BitInt, const N: usize>(game: &mut Game<T, N>);
-}
-
-pub struct MagicMoves;
+    /// The pieces `Chess` registers, in the order of the piece constants above.
+    fn pieces<T : BitInt, const N: usize>() -> Vec<Piece<T, N>>;
 
-impl ChessMoves for MagicMoves {
     fn actions<T : BitInt, const N: usize>(board: &mut Board<T, N>) -> Vec<Action> {
-        let mut actions = Vec::with_capacity(50);
-
-        Pawn.add_actions(board, &mut actions, 0);
-        Leaper(KnightMoves).add_actions(board, &mut actions, 1);
-        Magic(BishopMoves).add_actions(board, &mut actions, 2, 2);
-        Magic(RookMoves).add_actions(board, &mut actions, 3, 3);
-        Magic(BishopMoves).add_actions(board, &mut actions, 4, 2);
-        Magic(RookMoves).add_actions(board, &mut actions, 4, 3);
-        Leaper(KingMoves).add_actions(board, &mut actions, 5);
-        add_castling_actions(board, &mut actions, 5);
-
-        actions
+        board.piece_actions()
     }
-    
+
     fn attacks<T : BitInt, const N: usize>(board: &mut Board<T, N>, mask: BitBoard<T>) -> bool {
-        Pawn.attacks(board, 0, mask) ||
-        Leaper(KnightMoves).attacks(board, 1, mask) ||
-        Leaper(KingMoves).attacks(board, 5, mask) ||
-        Magic(BishopMoves).attacks(board, 2, 2, mask) ||
-        Magic(RookMoves).attacks(board, 3, 3, mask) ||
-        Magic(BishopMoves).attacks(board, 4, 2, mask) ||
-        Magic(RookMoves).attacks(board, 4, 3, mask)
-    }    
+        board.piece_attacks(mask)
+    }
+
+    fn attackers_to<T : BitInt, const N: usize>(board: &mut Board<T, N>, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        board.piece_attackers_to(square, team, occupancy)
+    }
 
     fn process<T : BitInt, const N: usize>(game: &mut Game<T, N>) {
-        Leaper(KnightMoves).process(game, 1);
-        Magic(BishopMoves).process(game, 2);
-        Magic(RookMoves).process(game, 3);
-        Leaper(KingMoves).process(game, 5);
+        game.process_pieces();
     }
 }
 
-pub struct SliderMoves;
-
-impl ChessMoves for SliderMoves {
-    fn actions<T : BitInt, const N: usize>(board: &mut Board<T, N>) -> Vec<Action> {
-        let mut actions = Vec::with_capacity(50);
-
-        Pawn.add_actions(board, &mut actions, 0);
-        Leaper(KnightMoves).add_actions(board, &mut actions, 1);
-        Slider(BishopMoves).add_actions(board, &mut actions, 2);
-        Slider(RookMoves).add_actions(board, &mut actions, 3);
-        Slider(QueenMoves).add_actions(board, &mut actions, 4);
-        Leaper(KingMoves).add_actions(board, &mut actions, 5);
-        add_castling_actions(board, &mut actions, 5);
+pub struct MagicMoves;
 
-        actions
+impl ChessMoves for MagicMoves {
+    fn pieces<T : BitInt, const N: usize>() -> Vec<Piece<T, N>> {
+        vec![
+            Piece::new("pawn", 'p', Pawn),
+            Piece::new("knight", 'n', Leaper(KnightMoves)),
+            Piece::new("bishop", 'b', Magic(BishopMoves)),
+            Piece::new("rook", 'r', Magic(RookMoves)),
+            Piece::new("queen", 'q', MagicQueen { bishop: BISHOP, rook: ROOK }),
+            Piece::new("king", 'k', King)
+        ]
     }
-    
-    fn attacks<T : BitInt, const N: usize>(board: &mut Board<T, N>, mask: BitBoard<T>) -> bool {
-        Pawn.attacks(board, 0, mask) ||
-        Leaper(KnightMoves).attacks(board, 1, mask) ||
-        Leaper(KingMoves).attacks(board, 5, mask) ||
-        Slider(BishopMoves).attacks(board, 2, mask) ||
-        Slider(RookMoves).attacks(board, 3, mask) ||
-        Slider(QueenMoves).attacks(board, 4, mask)
-    }    
+}
 
-    fn process<T : BitInt, const N: usize>(game: &mut Game<T, N>) {
-        Leaper(KnightMoves).process(game, 1);
-        Slider(BishopMoves).process(game, 2);
-        Slider(RookMoves).process(game, 3);
-        Slider(QueenMoves).process(game, 4);
-        Leaper(KingMoves).process(game, 5);
+pub struct SliderMoves;
+
+impl ChessMoves for SliderMoves {
+    fn pieces<T : BitInt, const N: usize>() -> Vec<Piece<T, N>> {
+        vec![
+            Piece::new("pawn", 'p', Pawn),
+            Piece::new("knight", 'n', Leaper(KnightMoves)),
+            Piece::new("bishop", 'b', Slider(BishopMoves)),
+            Piece::new("rook", 'r', Slider(RookMoves)),
+            Piece::new("queen", 'q', Slider(QueenMoves)),
+            Piece::new("king", 'k', King)
+        ]
     }
 }
 
@@ -171,73 +155,54 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
         Moves::attacks(board, mask)
     }    
 
-    fn play(&self, board: &mut Board<T, N>, act: Action) {
-        let piece_index = board.piece_at(act.from).expect("Couldn't find piece making move");
-
-        match piece_index {
-            PAWN => match act.info {
-                0 => make_chess_move(&mut board.state, act),
-                1 => make_en_passant_move(&mut board.state, act),
-                _ => make_promotion_move(&mut board.state, act)
-            },
-            KING => match act.info {
-                0 => make_chess_move(&mut board.state, act),
-                _ => make_castling_move(&mut board.state, act)
-            },
-            _ => make_chess_move(&mut board.state, act)
-        }
+    fn attackers_to(&self, board: &mut Board<T, N>, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Moves::attackers_to(board, square, team, occupancy)
     }
 
-    fn is_legal(&self, board: &mut Board<T, N>) -> bool {
-        let king = board.state.pieces[KING].and(board.state.opposite_team());
-        !board.attacks(king)
+    fn play(&self, board: &mut Board<T, N>, act: Action) {
+        let captures = board.state.opposite_team().and(BitBoard::index(act.to)).set();
+        let resets_clock = captures || act.piece as usize == PAWN;
+
+        board.make_piece_move(act);
+
+        board.state.halfmove_clock = if resets_clock { 0 } else { board.state.halfmove_clock + 1 };
     }
 
-    fn piece_map(&self) -> Vec<char> {
-        vec![
-            'p', 'n', 'b', 'r', 'q', 'k'
-        ]
+    fn is_legal(&self, board: &mut Board<T, N>) -> bool {
+        board.game.royalty.is_legal(board)
     }
 
     // TODO: Pawn promotions & castling handling for displays
 
     fn display_action(&self, board: &mut Board<T, N>, act: Action) -> Vec<String> {
+        if act.is_drop() {
+            let piece_map = board.game.piece_map();
+            return vec![
+                format!("{}@{}", piece_map[act.piece as usize].to_ascii_uppercase(), index_to_square(act.to))
+            ];
+        }
+
         let piece_index = board.piece_at(act.from).expect("Found piece making move");
 
-        match piece_index {
-            PAWN => match act.info {
-                0 | 1 => {
-                    vec![
-                        format!("{}{}", index_to_square(act.from), index_to_square(act.to))
-                    ]
-                },
-                _ => {
-                    let promotion = (act.info - 2) as usize;
-                    let piece_map = board.game.rules.piece_map();
-                    vec![
-                        format!("{}{}{}", index_to_square(act.from), index_to_square(act.to), piece_map[promotion])
-                    ]
-                }
-            },
-            KING => match act.info {
-                0 => {
-                    vec![
-                        format!("{}{}", index_to_square(act.from), index_to_square(act.to))
-                    ]
-                },
-                _ => {
-                    let king_dest = if act.to > act.from { act.from + 2 } else { act.from - 2 };
-                    vec![
-                        format!("{}{}", index_to_square(act.from), index_to_square(king_dest)),
-                        format!("{}{}", index_to_square(act.from), index_to_square(act.to))
-                    ]
-                }
-            },
-            _ => {
-                vec![
-                    format!("{}{}", index_to_square(act.from), index_to_square(act.to))
-                ]
-            }
+        if is_promotion(board, act) {
+            let promotion = (act.info - 2) as usize;
+            let piece_map = board.game.piece_map();
+            return vec![
+                format!("{}{}{}", index_to_square(act.from), index_to_square(act.to), piece_map[promotion])
+            ];
+        }
+
+        let castles = board.game.castling.is_some_and(|castling| castling.king == piece_index) && act.info != 0;
+        if castles {
+            let king_dest = if act.to > act.from { act.from + 2 } else { act.from - 2 };
+            vec![
+                format!("{}{}", index_to_square(act.from), index_to_square(king_dest)),
+                format!("{}{}", index_to_square(act.from), index_to_square(act.to))
+            ]
+        } else {
+            vec![
+                format!("{}{}", index_to_square(act.from), index_to_square(act.to))
+            ]
         }
     }
 
@@ -245,8 +210,14 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
         let parts: Vec<String> = pos.split(" ").map(|el| el.to_string()).collect();
 
 
-        // Piece Placement
-        board.load_pieces(&parts[0]);
+        // Piece Placement, with the pockets in brackets after it for games with drops
+        match parts[0].split_once('[') {
+            Some((placement, pockets)) => {
+                board.load_pieces(placement);
+                board.load_pockets(pockets.trim_end_matches(']'));
+            }
+            None => board.load_pieces(&parts[0])
+        }
 
         // Team to Move
         board.state.moving_team = if parts[1] == "w" { Team::White } else { Team::Black };
@@ -254,29 +225,33 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
         let left_side = BitBoard::edges_left(board.game.bounds, board.game.bounds.cols / 2);
         let right_side = BitBoard::edges_right(board.game.bounds, board.game.bounds.cols / 2);
 
-        // If a `Game` is constructed using `ChessProcessor`, we won't know the order of `king` and `rook`.
-        // Therefore, we can't be sure those pieces are at any given index.
-        // `required_pieces` allows us to save the index of them for use in `is_legal` so that we don't need to deal with hashes.
-        
         // Castling Rights
-        if !parts[2].contains("K") {
-            board.state.first_move = board.state.first_move
-                .xor(board.state.pieces[ROOK].and(board.state.white).and(right_side));
-        }
+        // Only the outermost rook on its back rank can hold a castling right; every other rook counts as moved.
+        let edges = board.game.edges[0];
+        let castling_rook = board.game.castling.map(|castling| castling.rook);
+        for (team, back_rank, king_side, queen_side) in [
+            (Team::White, edges.bottom, 'K', 'Q'),
+            (Team::Black, edges.top, 'k', 'q')
+        ] {
+            let Some(rook) = castling_rook else {
+                break;
+            };
 
-        if !parts[2].contains("Q") {
-            board.state.first_move = board.state.first_move
-                .xor(board.state.pieces[ROOK].and(board.state.white).and(left_side));
-        }
+            let rooks = board.state.pieces[rook].and(board.state.team(team));
+            let home_rooks = rooks.and(back_rank);
+            let mut castling_rooks = BitBoard::default();
 
-        if !parts[2].contains("k") {
-            board.state.first_move = board.state.first_move
-                .xor(board.state.pieces[ROOK].and(board.state.black).and(right_side));
-        }
+            let right_rooks = home_rooks.and(right_side);
+            if parts[2].contains(king_side) && right_rooks.set() {
+                castling_rooks = castling_rooks.or(BitBoard::index(right_rooks.bitscan_backward() as u16));
+            }
+
+            let left_rooks = home_rooks.and(left_side);
+            if parts[2].contains(queen_side) && left_rooks.set() {
+                castling_rooks = castling_rooks.or(BitBoard::index(left_rooks.bitscan_forward() as u16));
+            }
 
-        if !parts[2].contains("q") {
-            board.state.first_move = board.state.first_move
-                .xor(board.state.pieces[ROOK].and(board.state.black).and(left_side));
+            board.state.first_move = board.state.first_move.and_not(rooks.and_not(castling_rooks));
         }
         
         // En Passant
@@ -294,12 +269,21 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
             board.history.push(ActionRecord::Action(Action::from(one_back, one_forward, PAWN as u8).with_info(1)));
         }
 
+        // Halfmove Clock
+        board.state.halfmove_clock = parts.get(4).and_then(|clock| clock.parse().ok()).unwrap_or(0);
+
+        // Fullmove Number, starting from 1 and going up after black moves.
+        // The en passant move pushed above isn't one of the moves before the position, so it's taken back out.
+        let fullmoves = parts.get(5).and_then(|count| count.parse::<usize>().ok()).unwrap_or(1);
+        let plies = 2 * fullmoves.saturating_sub(1) + board.state.moving_team.index();
+        board.earlier_moves = plies.saturating_sub(board.history.len());
+
         Pawn.load(board, 0);
     }
 
     fn save(&self, board: &mut Board<T, N>) -> String {
         let mut piece_rows = Vec::new();
-        let piece_map = board.game.rules.piece_map();
+        let piece_map = board.game.piece_map();
         for row in (0..board.game.bounds.rows).rev() {
             let mut row_str = String::new();
             let mut empty_count = 0;
@@ -326,6 +310,10 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
                             Team::White => piece_char.to_ascii_uppercase(),
                             Team::Black => piece_char.to_ascii_lowercase(),
                         });
+
+                        if board.state.promoted.and(BitBoard::index(idx)).set() {
+                            row_str.push('~');
+                        }
     
                         found = true;
                         break;
@@ -344,7 +332,11 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
             piece_rows.push(row_str);
         }
     
-        let piece_placement = piece_rows.join("/");
+        let mut piece_placement = piece_rows.join("/");
+
+        if board.game.drops.enabled() {
+            piece_placement = format!("{}[{}]", piece_placement, board.save_pockets());
+        }
     
         // 2. Active Color
         let active_color = match board.state.moving_team {
@@ -353,21 +345,19 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
         };
     
         // 3. Castling Availability
-        let left_side = BitBoard::edges_left(board.game.bounds, board.game.bounds.cols / 2);
-        let right_side = BitBoard::edges_right(board.game.bounds, board.game.bounds.cols / 2);
-    
+        let castling_rights = extract_castling_rights(board);
         let mut castling = String::new();
     
-        if board.state.first_move.and(board.state.white.and(right_side)).or(board.state.pieces[ROOK].and(board.state.white).and(right_side)).empty() == false {
+        if castling_rights.white_king_side {
             castling.push('K');
         }
-        if board.state.first_move.and(board.state.white.and(left_side)).or(board.state.pieces[ROOK].and(board.state.white).and(left_side)).empty() == false {
+        if castling_rights.white_queen_side {
             castling.push('Q');
         }
-        if board.state.first_move.and(board.state.black.and(right_side)).or(board.state.pieces[ROOK].and(board.state.black).and(right_side)).empty() == false {
+        if castling_rights.black_king_side {
             castling.push('k');
         }
-        if board.state.first_move.and(board.state.black.and(left_side)).or(board.state.pieces[ROOK].and(board.state.black).and(left_side)).empty() == false {
+        if castling_rights.black_queen_side {
             castling.push('q');
         }
     
@@ -379,8 +369,8 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
         let mut en_passant = "-".to_string();
     
         if let Some(ActionRecord::Action(last_move)) = board.history.last() {
-            let last_piece_index = board.state.piece_at(last_move.to).expect("Found en passant piece");
-            let was_pawn_move = last_piece_index == PAWN;
+            // After castling, the last move's destination (where the rook was) is empty.
+            let was_pawn_move = board.state.piece_at(last_move.to) == Some(PAWN);
     
             if was_pawn_move {
                 let diff = last_move.to.abs_diff(last_move.from);
@@ -394,23 +384,22 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
             }
         }
     
-        format!("{} {} {} {} 0 1", piece_placement, active_color, castling, en_passant)
+        // 5. Halfmove Clock & 6. Fullmove Number
+        let fullmoves = (board.earlier_moves + board.history.len()) / 2 + 1;
+    
+        format!("{} {} {} {} {} {}", piece_placement, active_color, castling, en_passant, board.state.halfmove_clock, fullmoves)
     }
 
-    fn game_state(&self, board: &mut Board<T, N>, actions: &[Action]) -> crate::game::GameState {
-        if actions.len() == 0 {
-            let king = board.state.pieces[KING].and(board.state.team_to_move());
-            board.state.moving_team = board.state.moving_team.next();
-            let captures = board.attacks(king);
-            board.state.moving_team = board.state.moving_team.next();
+    fn game_state(&self, board: &mut Board<T, N>, actions: &[Action]) -> GameState {
+        board.game.royalty.game_state(board, actions)
+    }
 
-            if captures {
-                GameState::Win(board.state.moving_team.next())
-            } else {
-                GameState::Draw
-            }
-        } else {
-            GameState::Ongoing
+    /// Pawns start past their own back rank and promote on the other, so they can't stand on either.
+    fn unplaceable(&self, game: &Game<T, N>, piece: usize, _team: Team) -> BitBoard<T> {
+        let edges = game.edges[0];
+        match piece {
+            PAWN => edges.top.or(edges.bottom),
+            _ => BitBoard::default()
         }
     }
 
@@ -424,8 +413,13 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
         let castling_features = 16;
         let en_passant_features = (2 * squares) + 1;
 
+        // Pocket counts (up to a pocket holding a piece for every square) and promoted pieces come last,
+        // so games without drops keep the same hashes.
+        let pocket_features = teams * pieces * (squares + 1);
+        let promoted_features = squares;
+
         ZobristTable::generate(
-            piece_features + team_to_move_features + castling_features + en_passant_features,
+            piece_features + team_to_move_features + castling_features + en_passant_features + pocket_features + promoted_features,
             seed
         )
     }
@@ -485,6 +479,23 @@ impl<T : BitInt, Moves: ChessMoves, const N: usize> GameRules<T, N> for ChessPro
             attrs.push(features + (squares * 2));
         }
 
+        features += (squares * 2) + 1;
+
+        for team in [Team::White, Team::Black] {
+            for (piece, &count) in board.state.pockets[team.index()].iter().enumerate() {
+                if count > 0 {
+                    let count = (count as usize).min(squares);
+                    attrs.push(count + ((team.index() * pieces) + piece) * (squares + 1) + features);
+                }
+            }
+        }
+
+        features += 2 * pieces * (squares + 1);
+
+        for square in board.state.promoted.iter() {
+            attrs.push((square as usize) + features);
+        }
+
         table.compute(&attrs)
     }
 }
@@ -493,19 +504,27 @@ pub struct Chess<Moves> {
     _phantom: PhantomData<Moves>
 }
 
-impl <Moves: ChessMoves + 'static> GameTemplate for Chess<Moves> {
+impl <Moves: ChessMoves + Send + Sync + 'static> GameTemplate for Chess<Moves> {
     fn create<T : BitInt, const N: usize>() -> Game<T, N> {
         let bounds = Bounds::new(8, 8);
         let mut game = Game {
             rules: Box::new(ChessProcessor { _phantom: PhantomData::<Moves> }),
+            pieces: Moves::pieces(),
             bounds,
             default_pos: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1".to_string(),
-            lookup: [ const { vec![] }; N ],
+            promotion: PromotionRules::new(bounds, 1).promote(PAWN, vec![ KNIGHT, BISHOP, ROOK, QUEEN ], 1),
+            drops: DropRules::new(bounds),
+            royalty: Royalty::AllSafe(vec![ KING ]),
+            castling: None,
+            values: vec![ 100, 320, 330, 500, 900, 20000 ],
+            lookup: LookupSlots::new(),
             edges: vec![
                 BitBoard::edges(bounds, 1),
                 BitBoard::edges(bounds, 2)
             ],
-            magics: [ const { vec![] }; N ]
+            magics: LookupSlots::new(),
+            black_lookup: LookupSlots::indexes(),
+            reach: vec![]
         };
 
         Moves::process(&mut game);
@@ -518,9 +537,9 @@ impl <Moves: ChessMoves + 'static> GameTemplate for Chess<Moves> {
 mod tests {
     use std::collections::{HashMap, HashSet};
 
-    use crate::{chess::{Chess, MagicMoves}, game::{suite::{parse_suite, test_suite}, GameTemplate}};
+    use crate::{bitboard::BitBoard, chess::{Chess, MagicMoves, SliderMoves, KING, KNIGHT, PAWN, ROOK}, game::{action::square_to_index, differential::{run_differential, DifferentialOptions}, piece::Piece, suite::{parse_suite, test_suite}, Castling, GameTemplate, InvariantViolation, Team}};
 
-    use super::{suite::CHESS_SUITE, test_positions::TEST_POSITIONS};
+    use super::{pieces::leapers::{knight::KnightMoves, leaper::Leaper}, suite::CHESS_SUITE, test_positions::TEST_POSITIONS};
 
     #[test]
     fn chess_zobrist() {
@@ -571,4 +590,207 @@ mod tests {
         println!("{} collisions", collisions);
         assert_eq!(collisions, 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn chess_differential() {
+        let magic = Chess::<MagicMoves>::create::<u64, 6>();
+        let slider = Chess::<SliderMoves>::create::<u64, 6>();
+
+        let options = DifferentialOptions {
+            seed: 1,
+            games: 50,
+            max_ply: 150,
+            positions: CHESS_SUITE.split("\n")
+                .map(|line| line.split(";").next().expect("Suite line has a position").to_string())
+                .collect()
+        };
+
+        if let Err(failure) = run_differential(&[&magic, &slider], &options) {
+            panic!("Differential failure (seed {}): {:?}", failure.seed, failure);
+        }
+    }
+
+    #[test]
+    fn chess_validate() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.default();
+
+        assert!(board.state.validate().is_ok());
+
+        let e2 = BitBoard::index(square_to_index("e2").expect("Valid square"));
+        let e4 = BitBoard::index(square_to_index("e4").expect("Valid square"));
+
+        board.state.black = board.state.black.or(e2);
+        board.state.first_move = board.state.first_move.or(e4);
+        board.state.pieces[PAWN] = board.state.pieces[PAWN].or(e4);
+
+        let violations = board.state.validate().expect_err("Board should be invalid");
+        assert_eq!(violations, vec![
+            InvariantViolation::TeamsOverlap(e2),
+            InvariantViolation::UnownedPieces(e4),
+            InvariantViolation::EmptyFirstMoves(e4)
+        ]);
+    }
+
+    #[test]
+    fn chess_piece_registry() {
+        let chess = Chess::<SliderMoves>::create::<u64, 6>();
+        let board = chess.default();
+
+        assert_eq!(board.find_piece("rook"), Some(ROOK));
+        assert_eq!(board.find_piece("king"), Some(KING));
+        assert_eq!(board.find_piece("amazon"), None);
+        assert_eq!(chess.castling, Some(Castling { king: KING, rook: ROOK }));
+        assert_eq!(chess.piece_map(), vec![ 'p', 'n', 'b', 'r', 'q', 'k' ]);
+
+        // Positions are written with the symbols of the registered pieces, whatever they are.
+        let mut chess = Chess::<SliderMoves>::create::<u64, 6>();
+        chess.pieces[KNIGHT] = Piece::new("knight", 'h', Leaper(KnightMoves));
+        chess.process_pieces();
+
+        let fen = "rhbqkbhr/pppppppp/8/8/8/8/PPPPPPPP/RHBQKBHR w KQkq - 0 1";
+        let mut board = chess.load(fen);
+        assert_eq!(board.state.pieces[KNIGHT].count(), 4);
+        assert_eq!(chess.rules.save(&mut board), fen);
+    }
+
+    #[test]
+    fn chess_move_counters() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+
+        let fen = "r3k2r/8/8/3p4/8/8/8/R3K2R b KQkq - 12 40";
+        let mut board = chess.load(fen);
+        assert_eq!(chess.rules.save(&mut board), fen);
+
+        // Quiet moves count up the halfmove clock, and black's moves the fullmove number.
+        board.play_action("a8b8");
+        board.play_action("a1b1");
+        assert_eq!(chess.rules.save(&mut board), "1r2k2r/8/8/3p4/8/8/8/1R2K2R b Kk - 14 41");
+
+        // Pawn moves and captures reset the clock.
+        board.play_action("d5d4");
+        assert_eq!(chess.rules.save(&mut board), "1r2k2r/8/8/8/3p4/8/8/1R2K2R w Kk - 0 42");
+        board.play_action("b1b8");
+        assert_eq!(chess.rules.save(&mut board), "1R2k2r/8/8/8/3p4/8/8/4K2R b Kk - 0 42");
+
+        // The en passant square is written the same either way.
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 3";
+        let mut board = chess.load(fen);
+        assert_eq!(chess.rules.save(&mut board), fen);
+    }
+
+    #[test]
+    #[should_panic(expected = "Promoted pieces")]
+    fn promoted_pieces_need_drops() {
+        // Only games with drops track promoted pieces, so chess can't load one.
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        chess.load("4k3/8/8/8/8/8/8/Q~3K3 w - - 0 1");
+    }
+
+    #[test]
+    fn chess_attackers_to() {
+        let square = |name| square_to_index(name).expect("Valid square");
+
+        for chess in [ Chess::<MagicMoves>::create::<u64, 6>(), Chess::<SliderMoves>::create::<u64, 6>() ] {
+            // Every square `attacks` sees has attackers, and no other square does.
+            // Only squares without pieces of the attacking team are compared, since `attacks` may skip defended pieces.
+            for line in CHESS_SUITE.split("\n") {
+                let mut board = chess.load(line.split(";").next().expect("Suite line has a position"));
+                let occupancy = board.state.white.or(board.state.black);
+
+                for index in 0..64 {
+                    if board.state.team_to_move().and(BitBoard::index(index)).set() {
+                        continue;
+                    }
+
+                    let attackers = board.attackers_to(index, board.state.moving_team, occupancy);
+                    assert_eq!(attackers.set(), board.attacks(BitBoard::index(index)), "{} on {}", line, index);
+                    assert!(attackers.and_not(board.state.team_to_move()).empty());
+                }
+            }
+
+            // The rook behind the a2 rook only attacks a8 once a2 is taken out of the occupancy.
+            let mut board = chess.load("k7/8/8/8/8/8/R7/R6K w - - 0 1");
+            let occupancy = board.state.white.or(board.state.black);
+            let a1 = BitBoard::index(square("a1"));
+            let a2 = BitBoard::index(square("a2"));
+
+            assert_eq!(board.attackers_to(square("a8"), Team::White, occupancy), a2);
+            assert_eq!(board.attackers_to(square("a8"), Team::White, occupancy.and_not(a2)), a1);
+            assert_eq!(board.attackers_to(square("b1"), Team::White, occupancy), a1);
+            assert_eq!(board.attackers_to(square("b7"), Team::Black, occupancy), BitBoard::index(square("a8")));
+
+            let mut board = chess.load("k7/8/8/3p4/4P3/8/8/7K w - - 0 1");
+            let occupancy = board.state.white.or(board.state.black);
+            assert_eq!(board.attackers_to(square("d5"), Team::White, occupancy), BitBoard::index(square("e4")));
+            assert_eq!(board.attackers_to(square("e4"), Team::Black, occupancy), BitBoard::index(square("d5")));
+        }
+    }
+
+    #[test]
+    fn chess_checks() {
+        let square = |name| BitBoard::index(square_to_index(name).expect("Valid square"));
+
+        for chess in [ Chess::<MagicMoves>::create::<u64, 6>(), Chess::<SliderMoves>::create::<u64, 6>() ] {
+            // `gives_check` agrees with playing each action and looking for check.
+            for line in CHESS_SUITE.split("\n") {
+                let mut board = chess.load(line.split(";").next().expect("Suite line has a position"));
+
+                for action in board.legals() {
+                    let gives_check = board.gives_check(action);
+
+                    let state = board.play(action);
+                    let checkers = board.checkers();
+                    assert_eq!(gives_check, board.in_check(), "{} playing {}", line, board.display_uci_action(action));
+                    assert_eq!(gives_check, checkers.set());
+                    board.restore(state);
+                }
+            }
+
+            // A double check from the knight and the discovered rook.
+            let mut board = chess.load("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1");
+            let action = board.find_action("e4d6");
+            assert!(board.gives_check(action));
+            board.play(action);
+            assert!(board.in_check());
+            assert_eq!(board.checkers(), square("d6").or(square("e1")));
+
+            // Castling checks with the rook, and promoting checks with the new piece only.
+            let mut board = chess.load("5k2/8/8/8/8/8/8/4K2R w K - 0 1");
+            let castle = board.find_action("e1g1");
+            assert!(board.gives_check(castle));
+            assert!(!board.in_check());
+
+            let mut board = chess.load("7k/P7/8/8/8/8/8/K7 w - - 0 1");
+            let (queen, knight) = (board.find_action("a7a8q"), board.find_action("a7a8n"));
+            assert!(board.gives_check(queen));
+            assert!(!board.gives_check(knight));
+            assert_eq!(board.checkers(), BitBoard::default());
+        }
+    }
+
+    #[test]
+    fn castling_rights() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+
+        // Only the outermost rook on each side of the king keeps a castling right.
+        let mut board = chess.load("4k3/8/8/8/8/8/8/R1R1K1RR w KQ - 0 1");
+        assert!(board.state.first_move.and(BitBoard::index(0)).set());
+        assert!(board.state.first_move.and(BitBoard::index(2)).empty());
+        assert!(board.state.first_move.and(BitBoard::index(6)).empty());
+        assert!(board.state.first_move.and(BitBoard::index(7)).set());
+        assert_eq!(chess.rules.save(&mut board), "4k3/8/8/8/8/8/8/R1R1K1RR w KQ - 0 1");
+
+        // Once a king has moved, its rooks can't castle, even after it returns home.
+        let mut board = chess.load("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        for action in [ "e1e2", "e8d8", "e2e1" ] {
+            board.play_action(action);
+        }
+        assert_eq!(chess.rules.save(&mut board), "r2k3r/8/8/8/8/8/8/R3K2R b - - 3 2");
+
+        // Castling leaves the last action's destination empty, which saving the en passant square has to allow for.
+        let mut board = chess.load("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        board.play_action("e1g1");
+        assert_eq!(chess.rules.save(&mut board), "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+    }
+}
@@ -0,0 +1,550 @@
+use crate::{bitboard::{BitBoard, BitInt, Bounds, Edges}, game::{action::Action, piece::PieceRules, Board, Game, Team}};
+
+use super::sliders::{hopper::{hop_targets, process_hop_magics, Hop}, magics::{generate_magics, magics_feasible, Magic}, rider::symmetries, slider::{Slider, SliderMoves}};
+
+/// Which actions an atom can make: quiet moves (`m`), captures (`c`) or both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BetzaMode {
+    Both,
+    Move,
+    Capture
+}
+
+/// A single leap of a Betza piece, possibly repeated in a line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BetzaAtom {
+    /// Every `(x, y)` leap the atom makes, from white's perspective (where forward is up the board).
+    pub leaps: Vec<(i16, i16)>,
+    /// How many times a leap can be repeated in a line; `None` for riders without a limit.
+    pub range: Option<usize>,
+    pub mode: BetzaMode,
+    /// Set by `p` (cannon) and `g` (grasshopper), for atoms which must hop over a piece.
+    pub hop: Option<Hop>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BetzaError {
+    Empty,
+    UnknownAtom(char),
+    UnknownModifier(char),
+    /// Modifiers at the end of the notation, without an atom to apply to.
+    DanglingModifiers(String),
+    /// Direction modifiers that don't select any of an atom's leaps.
+    NoDirections(String)
+}
+
+/// The basic leaps an atom letter stands for.
+fn atom_leaps(atom: char) -> Option<Vec<(i16, i16)>> {
+    let leaps = match atom {
+        'W' | 'R' => vec![ (0, 1) ],
+        'F' | 'B' => vec![ (1, 1) ],
+        'K' | 'Q' => vec![ (0, 1), (1, 1) ],
+        'D' => vec![ (0, 2) ],
+        'N' => vec![ (1, 2) ],
+        'A' => vec![ (2, 2) ],
+        'H' => vec![ (0, 3) ],
+        'C' | 'L' => vec![ (1, 3) ],
+        'Z' | 'J' => vec![ (2, 3) ],
+        'G' => vec![ (3, 3) ],
+        _ => return None
+    };
+
+    Some(leaps)
+}
+
+fn matches_direction(direction: char, (x, y): (i16, i16)) -> bool {
+    match direction {
+        'f' => y > 0,
+        'b' => y < 0,
+        'l' => x < 0,
+        'r' => x > 0,
+        'v' => y != 0 && x.abs() <= y.abs(),
+        's' => x != 0 && y.abs() <= x.abs(),
+        _ => false
+    }
+}
+
+/// Splits direction modifiers into the directions they select, as one or two letters which must both hold.
+///
+/// Orthogonal atoms only have single letter directions, so `flW` is forward and left.
+/// Other atoms combine a vertical and horizontal letter into one direction, so `flF` is the forward-left diagonal.
+/// A doubled letter narrows the direction, so `ffN` is the two narrow forward knight leaps.
+fn split_directions(directions: &[char], orthogonal: bool) -> Vec<(char, Option<char>)> {
+    let mut split = vec![];
+    let mut index = 0;
+
+    while index < directions.len() {
+        let first = directions[index];
+        let second = directions.get(index + 1).copied().filter(|&second| {
+            !orthogonal && match first {
+                'f' | 'b' => matches!(second, 'l' | 'r' | 's') || second == first,
+                'l' | 'r' => matches!(second, 'f' | 'b' | 'v') || second == first,
+                _ => false
+            }
+        });
+
+        split.push((first, second));
+        index += if second.is_some() { 2 } else { 1 };
+    }
+
+    split
+}
+
+fn matches_split((first, second): (char, Option<char>), leap: (i16, i16)) -> bool {
+    let second = match second {
+        Some(second) if second == first => if matches!(first, 'f' | 'b') { 'v' } else { 's' },
+        Some(second) => second,
+        None => return matches_direction(first, leap)
+    };
+
+    matches_direction(first, leap) && matches_direction(second, leap)
+}
+
+fn make_atoms(atom: char, modifiers: &str, rider: bool, range: Option<usize>) -> Result<Vec<BetzaAtom>, BetzaError> {
+    let leaps = atom_leaps(atom).ok_or(BetzaError::UnknownAtom(atom))?;
+    let rider = rider || matches!(atom, 'R' | 'B' | 'Q');
+    let range = range.or(if rider { None } else { Some(1) });
+
+    let move_only = modifiers.contains('m');
+    let capture_only = modifiers.contains('c');
+    let mode = match (move_only, capture_only) {
+        (true, false) => BetzaMode::Move,
+        (false, true) => BetzaMode::Capture,
+        _ => BetzaMode::Both
+    };
+
+    let hop = if modifiers.contains('p') {
+        Some(Hop::Cannon)
+    } else if modifiers.contains('g') {
+        Some(Hop::Grasshopper)
+    } else {
+        None
+    };
+
+    let directions: Vec<char> = modifiers.chars().filter(|char| !matches!(char, 'm' | 'c' | 'p' | 'g')).collect();
+
+    let atoms: Vec<BetzaAtom> = leaps.into_iter()
+        .filter_map(|leap| {
+            let split = split_directions(&directions, leap.0 == 0);
+            let leaps: Vec<(i16, i16)> = symmetries(leap).into_iter()
+                .filter(|&leap| split.is_empty() || split.iter().any(|&direction| matches_split(direction, leap)))
+                .collect();
+
+            if leaps.is_empty() {
+                None
+            } else {
+                Some(BetzaAtom { leaps, range, mode, hop })
+            }
+        })
+        .collect();
+
+    if atoms.is_empty() {
+        return Err(BetzaError::NoDirections(format!("{}{}", modifiers, atom)));
+    }
+
+    Ok(atoms)
+}
+
+/// Parses Betza notation into its atoms.
+///
+/// Supported atoms are `W F D N A H C Z G` (with `L` and `J` as aliases of `C` and `Z`),
+/// plus the shorthands `K` (`WF`), `R` (`WW`), `B` (`FF`) and `Q` (`RB`).
+/// Doubling an atom (`NN`) makes it a rider, and a number after an atom (`R4`, `NN2`) limits its range.
+/// Atoms can be prefixed with `m` (moves only), `c` (captures only), `p` (hops like a cannon), `g` (hops like a grasshopper)
+/// and the directions `f b l r v s`.
+pub fn parse_betza(notation: &str) -> Result<Vec<BetzaAtom>, BetzaError> {
+    let chars: Vec<char> = notation.chars().filter(|char| !char.is_whitespace()).collect();
+    let mut atoms = vec![];
+    let mut modifiers = String::new();
+    let mut index = 0;
+
+    while index < chars.len() {
+        let char = chars[index];
+        index += 1;
+
+        if char.is_ascii_lowercase() {
+            if !"mcpgfblrvs".contains(char) {
+                return Err(BetzaError::UnknownModifier(char));
+            }
+
+            modifiers.push(char);
+            continue;
+        }
+
+        let rider = chars.get(index) == Some(&char);
+        if rider {
+            index += 1;
+        }
+
+        let digits: String = chars[index..].iter().take_while(|char| char.is_ascii_digit()).collect();
+        index += digits.len();
+        let range = digits.parse::<usize>().ok();
+
+        atoms.extend(make_atoms(char, &modifiers, rider, range)?);
+        modifiers.clear();
+    }
+
+    if !modifiers.is_empty() {
+        return Err(BetzaError::DanglingModifiers(modifiers));
+    }
+
+    if atoms.is_empty() {
+        return Err(BetzaError::Empty);
+    }
+
+    Ok(atoms)
+}
+
+/// The rays of a group of Betza leaps, built from board coordinates.
+/// Single leaps are rays of length one, so leapers and riders share the `Slider` and `Magic` lookups.
+#[derive(Clone)]
+pub struct BetzaRays {
+    pub leaps: Vec<((i16, i16), Option<usize>)>,
+    pub bounds: Bounds
+}
+
+impl SliderMoves for BetzaRays {
+    fn rays<T: BitInt>(&self, pos: BitBoard<T>, _edges: &Edges<T>) -> Vec<BitBoard<T>> {
+        let cols = self.bounds.cols as i16;
+        let rows = self.bounds.rows as i16;
+        let square = pos.bitscan_forward() as i16;
+
+        self.leaps.iter()
+            .map(|&((leap_x, leap_y), range)| {
+                let (mut x, mut y) = (square % cols, square / cols);
+                let mut ray = BitBoard::default();
+                let mut steps = 0;
+
+                while range.is_none_or(|range| steps < range) {
+                    x += leap_x;
+                    y += leap_y;
+                    if x < 0 || y < 0 || x >= cols || y >= rows {
+                        break;
+                    }
+
+                    ray = ray.or(BitBoard::index((y * cols + x) as u16));
+                    steps += 1;
+                }
+
+                ray
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct BetzaGroup {
+    /// `None` when both teams share the group.
+    team: Option<Team>,
+    mode: BetzaMode,
+    hop: Option<Hop>,
+    /// The group's rays, or its magics when it doesn't hop.
+    lookup: usize,
+    /// The lookup slot of the group's hop magics, which keep their rays in `lookup` for tracing lines.
+    hops: usize,
+    magic: bool
+}
+
+/// A piece compiled from Betza notation, e.g. `WfF`, `NN`, `mWcF` or `BN`.
+///
+/// Its atoms are grouped by mode, hop and team, and each group gets its own lookup slot with `Game::add_lookup`,
+/// using magics if `magics_feasible` allows it and rays otherwise.
+/// Black's leaps are white's flipped vertically.
+pub struct Betza {
+    pub atoms: Vec<BetzaAtom>,
+    groups: Vec<BetzaGroup>
+}
+
+impl Betza {
+    pub fn new(notation: &str) -> Result<Betza, BetzaError> {
+        Ok(Betza {
+            atoms: parse_betza(notation)?,
+            groups: vec![]
+        })
+    }
+
+    fn leaps(&self, team: Team, mode: BetzaMode, hop: Option<Hop>) -> Vec<((i16, i16), Option<usize>)> {
+        let mut leaps: Vec<((i16, i16), Option<usize>)> = self.atoms.iter()
+            .filter(|atom| atom.mode == mode && atom.hop == hop)
+            .flat_map(|atom| atom.leaps.iter().map(move |&(x, y)| match team {
+                Team::White => ((x, y), atom.range),
+                Team::Black => ((x, -y), atom.range)
+            }))
+            .collect();
+
+        leaps.sort();
+        leaps.dedup();
+        leaps
+    }
+
+    fn moving_groups(&self, team: Team) -> impl Iterator<Item = &BetzaGroup> {
+        self.groups.iter().filter(move |group| group.team.is_none_or(|group_team| group_team == team))
+    }
+
+    fn list_moves<T: BitInt, const N: usize>(group: &BetzaGroup, game: &Game<T, N>, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        match (group.magic, group.hop) {
+            (true, Some(_)) => Magic::<BetzaRays>::list_moves(game, group.hops, pos, blockers),
+            (true, None) => Magic::<BetzaRays>::list_moves(game, group.lookup, pos, blockers),
+            (false, Some(hop)) => hop_targets(game, group.lookup, pos, blockers, hop),
+            (false, None) => Slider::<BetzaRays>::list_moves(game, group.lookup, pos, blockers)
+        }
+    }
+
+    /// Slides along the rays of `group`, ignoring any hop.
+    fn slides<T: BitInt, const N: usize>(group: &BetzaGroup, game: &Game<T, N>, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        if group.magic && group.hop.is_none() {
+            Magic::<BetzaRays>::list_moves(game, group.lookup, pos, blockers)
+        } else {
+            Slider::<BetzaRays>::list_moves(game, group.lookup, pos, blockers)
+        }
+    }
+}
+
+impl<T: BitInt, const N: usize> PieceRules<T, N> for Betza {
+    fn process(&mut self, game: &mut Game<T, N>, _piece_index: usize) {
+        self.groups = vec![];
+
+        let kinds = [ BetzaMode::Both, BetzaMode::Move, BetzaMode::Capture ].into_iter()
+            .flat_map(|mode| [ None, Some(Hop::Cannon), Some(Hop::Grasshopper) ].map(|hop| (mode, hop)));
+
+        for (mode, hop) in kinds {
+            let white = self.leaps(Team::White, mode, hop);
+            let black = self.leaps(Team::Black, mode, hop);
+
+            let teams = if white == black {
+                vec![ (None, white) ]
+            } else {
+                vec![ (Some(Team::White), white), (Some(Team::Black), black) ]
+            };
+
+            for (team, leaps) in teams {
+                if leaps.is_empty() {
+                    continue;
+                }
+
+                let lookup = game.add_lookup();
+                Slider(BetzaRays { leaps, bounds: game.bounds }).process(game, lookup);
+
+                let magic = magics_feasible(game, lookup);
+                let hops = if magic && hop.is_some() { game.add_lookup() } else { lookup };
+                match (magic, hop) {
+                    (true, Some(hop)) => process_hop_magics(game, lookup, hops, hop),
+                    (true, None) => generate_magics(game, lookup, lookup, |game, pos, blockers| {
+                        Slider::<BetzaRays>::list_moves(game, lookup, pos, blockers)
+                    }),
+                    (false, _) => {}
+                }
+
+                self.groups.push(BetzaGroup { team, mode, hop, lookup, hops, magic });
+            }
+        }
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let team = board.state.team_to_move();
+        let enemies = board.state.opposite_team();
+        let blockers = team.or(enemies);
+        let piece = piece_index as u8;
+
+        for pos in board.state.pieces[piece_index].and(team).iter() {
+            let mut moves = BitBoard::default();
+
+            for group in self.moving_groups(board.state.moving_team) {
+                let group_moves = Betza::list_moves(group, board.game, pos as usize, blockers);
+                moves = moves.or(match group.mode {
+                    BetzaMode::Both => group_moves.and_not(team),
+                    BetzaMode::Move => group_moves.and_not(blockers),
+                    BetzaMode::Capture => group_moves.and(enemies)
+                });
+            }
+
+            for to in moves.iter() {
+                actions.push(Action::from(pos as u16, to as u16, piece));
+            }
+        }
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let pieces = board.state.pieces[piece_index].and(board.state.team_to_move());
+        if pieces.empty() {
+            return false;
+        }
+
+        let blockers = board.state.black.or(board.state.white);
+        self.moving_groups(board.state.moving_team)
+            .filter(|group| group.mode != BetzaMode::Move)
+            .any(|group| pieces.iter().any(|pos| {
+                if group.magic || group.hop.is_some() {
+                    Betza::list_moves(group, board.game, pos as usize, blockers).and(mask).set()
+                } else {
+                    Slider::<BetzaRays>::can_attack(board.game, group.lookup, pos as usize, blockers, mask)
+                }
+            }))
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        let mask = BitBoard::index(square);
+
+        board.state.pieces[piece_index]
+            .and(board.state.team(team))
+            .and(occupancy)
+            .iter()
+            .filter(|&pos| self.moving_groups(team)
+                .filter(|group| group.mode != BetzaMode::Move)
+                .any(|group| Betza::list_moves(group, board.game, pos as usize, occupancy).and(mask).set()))
+            .fold(BitBoard::default(), |attackers, pos| attackers.or(BitBoard::index(pos as u16)))
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, _piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.moving_groups(team.next())
+            .filter(|group| group.mode != BetzaMode::Move)
+            .fold(BitBoard::default(), |lines, group| {
+                let slides = Betza::slides(group, board.game, square as usize, occupancy);
+                let slides = match group.hop {
+                    // Hops can be blocked by the piece behind their screen as well.
+                    Some(_) => slides.or(Betza::slides(group, board.game, square as usize, occupancy.and_not(slides.and(occupancy)))),
+                    None => slides
+                };
+
+                lines.or(slides)
+            })
+    }
+
+    /// Every capturing group lands along its rays, which it slides all of on an empty board.
+    fn reach(&self, game: &Game<T, N>, _piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        self.moving_groups(team)
+            .filter(|group| group.mode != BetzaMode::Move)
+            .fold(BitBoard::default(), |reach, group| reach.or(Betza::slides(group, game, square as usize, BitBoard::default())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::{BitBoard, BitInt, Bounds}, chess::{pieces::{leapers::king::King, pawn::Pawn, sliders::{hopper::{hop_targets, Hop}, queen::QueenMoves, rider::NIGHTRIDER, rook::RookMoves, slider::Slider}, testing::{chess_with, square, squares}}, suite::CHESS_SUITE, Chess, ChessMoves, SliderMoves}, game::{piece::{Piece, PieceRules}, suite::{run_suite, SuiteOptions}, Game, GameTemplate}};
+
+    use super::{parse_betza, Betza, BetzaError, BetzaMode};
+
+    struct BetzaChess;
+
+    impl ChessMoves for BetzaChess {
+        fn pieces<T: BitInt, const N: usize>() -> Vec<Piece<T, N>> {
+            let betza = |notation| Betza::new(notation).expect("Valid notation");
+
+            vec![
+                Piece::new("pawn", 'p', Pawn),
+                Piece::new("knight", 'n', betza("N")),
+                Piece::new("bishop", 'b', betza("B")),
+                Piece::new("rook", 'r', betza("R")),
+                Piece::new("queen", 'q', betza("Q")),
+                Piece::new("king", 'k', King)
+            ]
+        }
+    }
+
+    #[test]
+    fn betza_parsing() {
+        let atoms = parse_betza("WfF").expect("Valid notation");
+        assert_eq!(atoms.len(), 2);
+        assert_eq!(atoms[0].leaps.len(), 4);
+        assert_eq!(atoms[1].leaps, vec![ (1, 1), (-1, 1) ]);
+
+        let nightrider = parse_betza("NN").expect("Valid notation");
+        assert_eq!(nightrider[0].leaps.len(), 8);
+        assert_eq!(nightrider[0].range, None);
+
+        let modes: Vec<BetzaMode> = parse_betza("mWcF").expect("Valid notation").iter().map(|atom| atom.mode).collect();
+        assert_eq!(modes, vec![ BetzaMode::Move, BetzaMode::Capture ]);
+
+        let shogi_knight = parse_betza("ffN").expect("Valid notation");
+        assert_eq!(shogi_knight[0].leaps, vec![ (1, 2), (-1, 2) ]);
+
+        assert_eq!(parse_betza("R4").expect("Valid notation")[0].range, Some(4));
+        assert_eq!(parse_betza("BN").expect("Valid notation").len(), 2);
+        assert_eq!(parse_betza("mRcpR").expect("Valid notation")[1].hop, Some(Hop::Cannon));
+
+        assert_eq!(parse_betza("X").err(), Some(BetzaError::UnknownAtom('X')));
+        assert_eq!(parse_betza("Wf").err(), Some(BetzaError::DanglingModifiers("f".to_string())));
+        assert_eq!(parse_betza("flW").map(|atoms| atoms[0].leaps.len()), Ok(2));
+    }
+
+    #[test]
+    fn betza_chess_suite() {
+        // Chess with Betza pieces finds the same perft results as chess itself.
+        let betza = Chess::<BetzaChess>::create::<u64, 6>();
+        let result = run_suite(CHESS_SUITE, &betza, SuiteOptions { max_depth: Some(3), ..Default::default() });
+
+        assert!(result.passed(), "{:?}", result.failures());
+    }
+
+    #[test]
+    fn betza_divergent_and_directional() {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+
+        // Replace the queen with a piece moving as a wazir, capturing as a ferz, and one which only steps forward.
+        game.pieces[4] = Piece::new("queen", 'q', Betza::new("mWcF").expect("Valid notation"));
+        game.pieces[1] = Piece::new("knight", 'n', Betza::new("fW").expect("Valid notation"));
+        game.process_pieces();
+
+        let mut board = game.load("4k3/8/8/2pp4/3Q4/8/8/4K3 w - - 0 1");
+        let mut queen: Vec<String> = board.legals().into_iter()
+            .filter(|action| action.piece == 4)
+            .map(|action| board.display_uci_action(action))
+            .collect();
+        queen.sort();
+        assert_eq!(queen, vec![ "d4c4", "d4c5", "d4d3", "d4e4" ]);
+
+        let mut board = game.load("4k3/8/4n3/8/8/4N3/8/4K3 w - - 0 1");
+        let white: Vec<String> = board.legals().into_iter()
+            .filter(|action| action.piece == 1)
+            .map(|action| board.display_uci_action(action))
+            .collect();
+        assert_eq!(white, vec![ "e3e4".to_string() ]);
+
+        let mut board = game.load("4k3/8/4n3/8/8/4N3/8/4K3 b - - 0 1");
+        let black: Vec<String> = board.legals().into_iter()
+            .filter(|action| action.piece == 1)
+            .map(|action| board.display_uci_action(action))
+            .collect();
+        assert_eq!(black, vec![ "e6e5".to_string() ]);
+    }
+
+    /// Checks that `notation` gets magics on `game`, which list `expected` from `pos` with `blockers`,
+    /// as do the rays (or hops) in `rays`.
+    fn assert_magics(game: &mut Game<u64, 6>, notation: &str, rays: usize, hop: Option<Hop>, pos: usize, blockers: BitBoard<u64>, expected: BitBoard<u64>) {
+        let mut betza = Betza::new(notation).expect("Valid notation");
+        betza.process(game, 0);
+        assert!(betza.groups.iter().all(|group| group.magic));
+
+        let from_rays = match hop {
+            Some(hop) => hop_targets(game, rays, pos, blockers, hop),
+            None => Slider::<RookMoves>::list_moves(game, rays, pos, blockers)
+        };
+
+        assert_eq!(Betza::list_moves(&betza.groups[0], game, pos, blockers), expected, "{}", notation);
+        assert_eq!(from_rays, expected, "{}", notation);
+    }
+
+    #[test]
+    fn small_board_magics() {
+        let mut game = chess_with(Bounds::new(6, 6), vec![]);
+
+        // From a1, the nightrider is stopped on b3, but goes on through c2 to e3 before leaving the board.
+        let nightrider = game.add_lookup();
+        Slider(NIGHTRIDER).process(&mut game, nightrider);
+        assert_magics(&mut game, "NN", nightrider, None, square(6, "a1"), squares(6, &[ "b3", "f6" ]), squares(6, &[ "b3", "c2", "e3" ]));
+
+        // From c3, screens on the a3 and c1 edges have nothing behind them.
+        let blockers = squares(6, &[ "c4", "a3", "e3", "f3", "c1" ]);
+
+        let rook = game.add_lookup();
+        Slider(RookMoves).process(&mut game, rook);
+        assert_magics(&mut game, "pR", rook, Some(Hop::Cannon), square(6, "c3"), blockers, squares(6, &[ "c5", "c6", "f3" ]));
+
+        // The grasshopper also hops the diagonal hurdles on d4, b2 and b4, but has none to hop towards e1.
+        let blockers = blockers.or(squares(6, &[ "d4", "b2", "b4" ]));
+
+        let queen = game.add_lookup();
+        Slider(QueenMoves).process(&mut game, queen);
+        assert_magics(&mut game, "gQ", queen, Some(Hop::Grasshopper), square(6, "c3"), blockers, squares(6, &[ "c5", "f3", "e5", "a1", "a5" ]));
+    }
+}
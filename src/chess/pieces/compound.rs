@@ -0,0 +1,211 @@
+use crate::{bitboard::{BitBoard, BitInt}, game::{action::Action, piece::PieceRules, Board, Game, Team}};
+
+use super::{leapers::{king::KingMoves, knight::KnightMoves, leaper::Leaper}, sliders::{bishop::BishopMoves, magics::Magic, rook::RookMoves}};
+
+/// A way of moving which can be combined with others in a `Compound`.
+///
+/// Unlike `PieceRules`, a mover isn't tied to a piece index, so it's told which lookup slot its tables live in.
+pub trait Mover<T : BitInt, const N: usize> : Send + Sync {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize);
+
+    /// Every square reachable by `team` from `pos` with `blockers`, including squares of the piece's own team.
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T>;
+
+    /// Whether a piece of `team` on `pos` can see `mask`.
+    fn attacks(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>, mask: BitBoard<T>) -> bool {
+        self.moves(game, lookup_index, team, pos, blockers).and(mask).set()
+    }
+
+    /// The lines a piece of `team` could attack `pos` along, traced out from `pos` up to the first piece of `occupancy` on each.
+    /// Movers that can't be blocked (like leapers) have none.
+    fn lines(&self, _game: &Game<T, N>, _lookup_index: usize, _team: Team, _pos: usize, _occupancy: BitBoard<T>) -> BitBoard<T> {
+        BitBoard::default()
+    }
+
+    /// Every square a piece of `team` on `pos` could see on some board, like `PieceRules::reach`.
+    /// Defaults to its moves on an empty board, which movers needing a piece to move over (like hoppers) override.
+    fn reach(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize) -> BitBoard<T> {
+        self.moves(game, lookup_index, team, pos, BitBoard::default())
+    }
+}
+
+/// The pieces of type `piece_index` on `team` which `mover` attacks `square` from, with `occupancy` as the blockers.
+pub fn mover_attackers_to<T : BitInt, const N: usize>(
+    mover: &impl Mover<T, N>,
+    board: &Board<T, N>,
+    piece_index: usize,
+    lookup_index: usize,
+    square: u16,
+    team: Team,
+    occupancy: BitBoard<T>
+) -> BitBoard<T> {
+    let mask = BitBoard::index(square);
+
+    board.state.pieces[piece_index]
+        .and(board.state.team(team))
+        .and(occupancy)
+        .iter()
+        .filter(|&pos| mover.attacks(board.game, lookup_index, team, pos as usize, occupancy, mask))
+        .fold(BitBoard::default(), |attackers, pos| attackers.or(BitBoard::index(pos as u16)))
+}
+
+/// A piece moving as the union of two movers, like the archbishop (bishop and knight).
+/// Compounds are movers themselves, so they can be nested to combine more than two.
+///
+/// The first mover uses the compound's own lookup slot and the second gets one from `Game::add_lookup`,
+/// while actions, attacks and hashing all go through the single piece index of the compound.
+pub struct Compound<A, B> {
+    pub first: A,
+    pub second: B,
+    second_lookup: usize
+}
+
+impl<A, B> Compound<A, B> {
+    pub fn new(first: A, second: B) -> Compound<A, B> {
+        Compound { first, second, second_lookup: 0 }
+    }
+}
+
+impl<T : BitInt, const N: usize, A : Mover<T, N>, B : Mover<T, N>> Mover<T, N> for Compound<A, B> {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize) {
+        self.first.process(game, lookup_index);
+        self.second_lookup = game.add_lookup();
+        self.second.process(game, self.second_lookup);
+    }
+
+    #[inline(always)]
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        self.first.moves(game, lookup_index, team, pos, blockers)
+            .or(self.second.moves(game, self.second_lookup, team, pos, blockers))
+    }
+
+    #[inline(always)]
+    fn attacks(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>, mask: BitBoard<T>) -> bool {
+        self.first.attacks(game, lookup_index, team, pos, blockers, mask) ||
+        self.second.attacks(game, self.second_lookup, team, pos, blockers, mask)
+    }
+
+    fn lines(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.first.lines(game, lookup_index, team, pos, occupancy)
+            .or(self.second.lines(game, self.second_lookup, team, pos, occupancy))
+    }
+
+    fn reach(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize) -> BitBoard<T> {
+        self.first.reach(game, lookup_index, team, pos)
+            .or(self.second.reach(game, self.second_lookup, team, pos))
+    }
+}
+
+impl<T : BitInt, const N: usize, A : Mover<T, N>, B : Mover<T, N>> PieceRules<T, N> for Compound<A, B> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        Mover::process(self, game, piece_index);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let team = board.state.team_to_move();
+        let blockers = board.state.black.or(board.state.white);
+        let piece = piece_index as u8;
+
+        for pos in board.state.pieces[piece_index].and(team).iter() {
+            let moves = self.moves(board.game, piece_index, board.state.moving_team, pos as usize, blockers).and_not(team);
+
+            for to in moves.iter() {
+                actions.push(Action::from(pos as u16, to as u16, piece));
+            }
+        }
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let blockers = board.state.black.or(board.state.white);
+
+        board.state.pieces[piece_index]
+            .and(board.state.team_to_move())
+            .iter()
+            .any(|pos| Mover::attacks(self, board.game, piece_index, board.state.moving_team, pos as usize, blockers, mask))
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Mover::lines(self, board.game, piece_index, team, square as usize, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+/// Bishop and knight.
+pub fn archbishop() -> Compound<Magic<BishopMoves>, Leaper<KnightMoves>> {
+    Compound::new(Magic(BishopMoves), Leaper(KnightMoves))
+}
+
+/// Rook and knight.
+pub fn chancellor() -> Compound<Magic<RookMoves>, Leaper<KnightMoves>> {
+    Compound::new(Magic(RookMoves), Leaper(KnightMoves))
+}
+
+/// Queen and knight.
+pub fn amazon() -> Compound<Compound<Magic<BishopMoves>, Magic<RookMoves>>, Leaper<KnightMoves>> {
+    Compound::new(Compound::new(Magic(BishopMoves), Magic(RookMoves)), Leaper(KnightMoves))
+}
+
+/// King and knight, without castling.
+pub fn centaur() -> Compound<Leaper<KingMoves>, Leaper<KnightMoves>> {
+    Compound::new(Leaper(KingMoves), Leaper(KnightMoves))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{pieces::{betza::Betza, testing::{chess_variant, piece_moves}}, BISHOP, KNIGHT, QUEEN, ROOK}, game::piece::Piece};
+
+    use super::{amazon, archbishop, centaur, chancellor};
+
+    #[test]
+    fn compound_moves() {
+        let compound = chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', centaur())),
+            (BISHOP, Piece::new("bishop", 'b', archbishop())),
+            (ROOK, Piece::new("rook", 'r', chancellor())),
+            (QUEEN, Piece::new("queen", 'q', amazon()))
+        ]);
+
+        let betza = |notation| Betza::new(notation).expect("Valid notation");
+        let betza = chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', betza("KN"))),
+            (BISHOP, Piece::new("bishop", 'b', betza("BN"))),
+            (ROOK, Piece::new("rook", 'r', betza("RN"))),
+            (QUEEN, Piece::new("queen", 'q', betza("QN")))
+        ]);
+
+        // From d4, every knight leap is open, the e5 and b2 pawns block diagonals, and the d6 pawn is captured.
+        let expected = [
+            (KNIGHT, "N", vec![
+                "d4b3", "d4b5", "d4c2", "d4c3", "d4c4", "d4c5", "d4c6", "d4d3",
+                "d4d5", "d4e2", "d4e3", "d4e4", "d4e6", "d4f3", "d4f5"
+            ]),
+            (BISHOP, "B", vec![
+                "d4a7", "d4b3", "d4b5", "d4b6", "d4c2", "d4c3", "d4c5", "d4c6",
+                "d4e2", "d4e3", "d4e6", "d4f2", "d4f3", "d4f5", "d4g1"
+            ]),
+            (ROOK, "R", vec![
+                "d4a4", "d4b3", "d4b4", "d4b5", "d4c2", "d4c4", "d4c6", "d4d1", "d4d2", "d4d3",
+                "d4d5", "d4d6", "d4e2", "d4e4", "d4e6", "d4f3", "d4f4", "d4f5", "d4g4", "d4h4"
+            ]),
+            (QUEEN, "Q", vec![
+                "d4a4", "d4a7", "d4b3", "d4b4", "d4b5", "d4b6", "d4c2", "d4c3", "d4c4",
+                "d4c5", "d4c6", "d4d1", "d4d2", "d4d3", "d4d5", "d4d6", "d4e2", "d4e3",
+                "d4e4", "d4e6", "d4f2", "d4f3", "d4f4", "d4f5", "d4g1", "d4g4", "d4h4"
+            ])
+        ];
+
+        for game in [ compound, betza ] {
+            for (piece, symbol, moves) in &expected {
+                let mut board = game.load(&format!("4k3/8/3p4/4P3/3{}4/8/1P6/4K3 w - - 0 1", symbol));
+                assert_eq!(&piece_moves(&mut board, *piece as u8), moves, "{}", symbol);
+            }
+        }
+    }
+}
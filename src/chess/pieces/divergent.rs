@@ -0,0 +1,166 @@
+use crate::{bitboard::{BitBoard, BitInt}, game::{action::Action, piece::PieceRules, Board, Game, Team}};
+
+use super::compound::{mover_attackers_to, Mover};
+
+/// A piece which moves quietly with one mover and captures with another, like `mRcB` in Betza notation.
+///
+/// The quiet mover uses the piece's own lookup slot and the capturing mover gets one from `Game::add_lookup`.
+/// Only the capture pattern counts as attacking, so a divergent piece never gives check with its quiet moves.
+pub struct Divergent<M, C> {
+    pub moves: M,
+    pub captures: C,
+    capture_lookup: usize
+}
+
+impl<M, C> Divergent<M, C> {
+    pub fn new(moves: M, captures: C) -> Divergent<M, C> {
+        Divergent { moves, captures, capture_lookup: 0 }
+    }
+}
+
+impl<T : BitInt, const N: usize, M : Mover<T, N>, C : Mover<T, N>> PieceRules<T, N> for Divergent<M, C> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        self.moves.process(game, piece_index);
+        self.capture_lookup = game.add_lookup();
+        self.captures.process(game, self.capture_lookup);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let moving_team = board.state.moving_team;
+        let team = board.state.team_to_move();
+        let enemies = board.state.opposite_team();
+        let blockers = team.or(enemies);
+        let piece = piece_index as u8;
+
+        for pos in board.state.pieces[piece_index].and(team).iter() {
+            let quiet = self.moves.moves(board.game, piece_index, moving_team, pos as usize, blockers).and_not(blockers);
+            let captures = self.captures.moves(board.game, self.capture_lookup, moving_team, pos as usize, blockers).and(enemies);
+
+            for to in quiet.or(captures).iter() {
+                actions.push(Action::from(pos as u16, to as u16, piece));
+            }
+        }
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let blockers = board.state.black.or(board.state.white);
+
+        board.state.pieces[piece_index]
+            .and(board.state.team_to_move())
+            .iter()
+            .any(|pos| self.captures.attacks(board.game, self.capture_lookup, board.state.moving_team, pos as usize, blockers, mask))
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(&self.captures, board, piece_index, self.capture_lookup, square, team, occupancy)
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, _piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.captures.lines(board.game, self.capture_lookup, team, square as usize, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, _piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        self.captures.reach(game, self.capture_lookup, team, square as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::{pieces::{betza::Betza, leapers::leaper::{Leaper, LeaperMoves}, sliders::{bishop::BishopMoves, hopper::{Hop, Hopper}, leap, magics::Magic, rook::RookMoves, slider::Slider}, testing::{chess_variant, piece_moves}}, BISHOP, KNIGHT, QUEEN}, game::{action::square_to_index, piece::Piece, Board, Game}};
+
+    use super::Divergent;
+
+    /// Steps diagonally forward, as a berolina pawn moves.
+    struct ForwardDiagonal;
+    /// Steps straight forward, as a berolina pawn captures.
+    struct ForwardStep;
+
+    impl LeaperMoves for ForwardDiagonal {
+        fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T> {
+            leap(pos, edges, (1, 1)).or(leap(pos, edges, (-1, 1)))
+        }
+
+        fn directional(&self) -> bool {
+            true
+        }
+    }
+
+    impl LeaperMoves for ForwardStep {
+        fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T> {
+            leap(pos, edges, (0, 1))
+        }
+
+        fn directional(&self) -> bool {
+            true
+        }
+    }
+
+    fn divergent_chess() -> Game<u64, 6> {
+        chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', Divergent::new(Leaper(ForwardDiagonal), Leaper(ForwardStep)))),
+            (BISHOP, Piece::new("bishop", 'b', Divergent::new(Magic(RookMoves), Slider(BishopMoves)))),
+            (QUEEN, Piece::new("queen", 'q', Divergent::new(Magic(RookMoves), Hopper::new(RookMoves, Hop::Cannon))))
+        ])
+    }
+
+    #[test]
+    fn divergent_moves_and_captures() {
+        let game = divergent_chess();
+
+        // The berolina-like knight moves diagonally forward, but only captures straight ahead.
+        let mut board = game.load("4k3/8/8/2ppp3/3N4/8/8/4K3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, 1), vec![ "d4d5" ]);
+
+        // The "mRcB" bishop slides along the d-file, and captures only on the diagonal.
+        let mut board = game.load("4k3/8/8/2p5/3B4/8/3n4/4K3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, 2), vec![ "d4a4", "d4b4", "d4c4", "d4c5", "d4d3", "d4d5", "d4d6", "d4d7", "d4d8", "d4e4", "d4f4", "d4g4", "d4h4" ]);
+
+        // Its quiet moves don't attack, so the king can step onto the file the bishop slides along,
+        // but not onto the diagonal it captures along.
+        let mut board = game.load("4k3/8/8/8/B7/8/8/3BK3 b - - 0 1");
+        let king = piece_moves(&mut board, 5);
+        assert!(king.contains(&"e8d8".to_string()));
+        assert!(!king.contains(&"e8d7".to_string()));
+    }
+
+    /// The legal actions of `piece` split into quiet moves and captures, each sorted in UCI notation.
+    fn quiet_and_captures(board: &mut Board<u64, 6>, piece: usize) -> (Vec<String>, Vec<String>) {
+        let enemies = board.state.opposite_team();
+        let (captures, quiet) = piece_moves(board, piece as u8).into_iter()
+            .partition(|action| {
+                let to = square_to_index(&action[2..4]).expect("Valid square");
+                enemies.and(BitBoard::index(to)).set()
+            });
+
+        (quiet, captures)
+    }
+
+    #[test]
+    fn divergent_betza() {
+        let betza = |notation| Betza::new(notation).expect("Valid notation");
+        let betza = chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', betza("mfFcfW"))),
+            (BISHOP, Piece::new("bishop", 'b', betza("mRcB"))),
+            (QUEEN, Piece::new("queen", 'q', betza("mRcpR")))
+        ]);
+
+        for game in [ divergent_chess(), betza ] {
+            // The knight can't move onto its own c5 pawn, and can't capture diagonally either.
+            let mut board = game.load("4k3/8/8/2Pp4/3N4/8/8/4K3 w - - 0 1");
+            assert_eq!(quiet_and_captures(&mut board, KNIGHT), (vec![ "d4e5".to_string() ], vec![ "d4d5".to_string() ]));
+
+            // The bishop slides up to its own d2 pawn, and captures on each diagonal that ends in an enemy.
+            let mut board = game.load("4k3/8/1p3p2/8/3B4/8/1n1P4/4K3 w - - 0 1");
+            let (quiet, captures) = quiet_and_captures(&mut board, BISHOP);
+            assert_eq!(quiet, vec![ "d4a4", "d4b4", "d4c4", "d4d3", "d4d5", "d4d6", "d4d7", "d4d8", "d4e4", "d4f4", "d4g4", "d4h4" ]);
+            assert_eq!(captures, vec![ "d4b2", "d4b6", "d4f6" ]);
+
+            // The queen slides up to every piece, but only captures by hopping over the d3 and g4 screens,
+            // as nothing stands behind the b4 knight or d7 rook.
+            let mut board = game.load("4k3/3r4/8/8/1n1Q2pp/3p4/8/3bK3 w - - 0 1");
+            let (quiet, captures) = quiet_and_captures(&mut board, QUEEN);
+            assert_eq!(quiet, vec![ "d4c4", "d4d5", "d4d6", "d4e4", "d4f4" ]);
+            assert_eq!(captures, vec![ "d4d1", "d4h4" ]);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::sliders::leap};
+
+use super::leaper::LeaperMoves;
+
+fn leap_all<T: BitInt>(pos: BitBoard<T>, edges: &Edges<T>, leaps: &[(i16, i16)]) -> BitBoard<T> {
+    leaps.iter().fold(BitBoard::default(), |moves, &leap_by| moves.or(leap(pos, edges, leap_by)))
+}
+
+/// The shogi gold general: steps orthogonally, or diagonally forward.
+#[derive(Copy, Clone)]
+pub struct GoldMoves;
+
+impl LeaperMoves for GoldMoves {
+    fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T> {
+        leap_all(pos, edges, &[ (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0), (0, -1) ])
+    }
+
+    fn directional(&self) -> bool {
+        true
+    }
+}
+
+/// The shogi silver general (and the Makruk khon): steps diagonally, or straight forward.
+#[derive(Copy, Clone)]
+pub struct SilverMoves;
+
+impl LeaperMoves for SilverMoves {
+    fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T> {
+        leap_all(pos, edges, &[ (0, 1), (1, 1), (-1, 1), (1, -1), (-1, -1) ])
+    }
+
+    fn directional(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{pieces::{betza::Betza, leapers::leaper::Leaper, sliders::{lance::LanceMoves, magics::Magic, slider::Slider}, testing::{chess_variant, piece_moves}}, BISHOP, KNIGHT, QUEEN, ROOK}, game::piece::Piece};
+
+    use super::{GoldMoves, SilverMoves};
+
+    #[test]
+    fn generals_move_forward_for_each_team() {
+        let game = chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', Leaper(SilverMoves))),
+            (BISHOP, Piece::new("bishop", 'b', Leaper(GoldMoves))),
+            (ROOK, Piece::new("rook", 'r', Slider(LanceMoves))),
+            (QUEEN, Piece::new("queen", 'q', Slider(LanceMoves)))
+        ]);
+
+        let mut board = game.load("4k3/8/8/3n4/3N4/8/8/4K3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, 1), vec![ "d4c3", "d4c5", "d4d5", "d4e3", "d4e5" ]);
+
+        // Black's silver is mirrored, so it steps straight down and diagonally up.
+        let mut board = game.load("4k3/8/8/3n4/3N4/8/8/4K3 b - - 0 1");
+        assert_eq!(piece_moves(&mut board, 1), vec![ "d5c4", "d5c6", "d5d4", "d5e4", "d5e6" ]);
+
+        let mut board = game.load("4k3/8/8/3b4/8/8/8/4K3 b - - 0 1");
+        assert_eq!(piece_moves(&mut board, 2), vec![ "d5c4", "d5c5", "d5d4", "d5d6", "d5e4", "d5e5" ]);
+
+        // A lance only slides forward, and black's lance on e7 gives check down the file.
+        let mut board = game.load("8/4q3/8/8/8/8/8/4K2k w - - 0 1");
+        let legals = piece_moves(&mut board, 5);
+        assert!(!legals.contains(&"e1e2".to_string()));
+        assert!(legals.contains(&"e1d1".to_string()));
+    }
+
+    #[test]
+    fn directional_generators() {
+        let generals = || vec![
+            (KNIGHT, Piece::new("knight", 'n', Leaper(SilverMoves))),
+            (BISHOP, Piece::new("bishop", 'b', Leaper(GoldMoves)))
+        ];
+
+        let mut pieces = generals();
+        pieces.push((ROOK, Piece::new("rook", 'r', Slider(LanceMoves))));
+        pieces.push((QUEEN, Piece::new("queen", 'q', Slider(LanceMoves))));
+        let slider = chess_variant(pieces);
+
+        let mut pieces = generals();
+        pieces.push((ROOK, Piece::new("rook", 'r', Magic(LanceMoves))));
+        pieces.push((QUEEN, Piece::new("queen", 'q', Magic(LanceMoves))));
+        let magic = chess_variant(pieces);
+
+        let betza = |notation| Betza::new(notation).expect("Valid notation");
+        let betza = chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', betza("FfW"))),
+            (BISHOP, Piece::new("bishop", 'b', betza("WfF"))),
+            (ROOK, Piece::new("rook", 'r', betza("fR"))),
+            (QUEEN, Piece::new("queen", 'q', betza("fR")))
+        ]);
+
+        for game in [ slider, magic, betza ] {
+            // White's silver steps up and diagonally, blocked by its own c5 pawn but capturing on e5.
+            let mut board = game.load("4k3/8/8/2P1p3/3N4/8/8/4K3 w - - 0 1");
+            assert_eq!(piece_moves(&mut board, KNIGHT as u8), vec![ "d4c3", "d4d5", "d4e3", "d4e5" ]);
+
+            // Black's gold steps down and diagonally down, blocked by its own d6 pawn but capturing on c4.
+            let mut board = game.load("4k3/8/3p4/3b4/2P5/8/8/4K3 b - - 0 1");
+            assert_eq!(piece_moves(&mut board, BISHOP as u8), vec![ "d5c4", "d5c5", "d5d4", "d5e4", "d5e5" ]);
+
+            // White's lance slides up the file to capture on d6, and black's slides down it to capture on d3.
+            let mut board = game.load("4k3/8/3p4/8/8/8/3R4/4K3 w - - 0 1");
+            assert_eq!(piece_moves(&mut board, ROOK as u8), vec![ "d2d3", "d2d4", "d2d5", "d2d6" ]);
+
+            let mut board = game.load("4k3/3q4/8/8/8/3P4/8/4K3 b - - 0 1");
+            assert_eq!(piece_moves(&mut board, QUEEN as u8), vec![ "d7d3", "d7d4", "d7d5", "d7d6" ]);
+        }
+    }
+}
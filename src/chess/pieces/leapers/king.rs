@@ -1,6 +1,6 @@
-use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::ROOK, game::{action::{index_to_square, make_chess_move, Action}, Board, BoardState, Game, Team}};
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::compound::{mover_attackers_to, Mover}, game::{action::{index_to_square, make_chess_move, Action}, piece::PieceRules, Board, BoardState, Castling, Game, Team}};
 
-use super::leaper::LeaperMoves;
+use super::leaper::{Leaper, LeaperMoves};
 
 pub fn make_castling_move<T: BitInt, const N: usize>(state: &mut BoardState<T, N>, action: Action) {
     let piece_index = action.piece as usize;
@@ -30,16 +30,29 @@ pub fn make_castling_move<T: BitInt, const N: usize>(state: &mut BoardState<T, N
             state.black = state.black.xor(king).xor(rook).or(king_relocated).or(rook_relocated)
         }
     }
+
+    // Neither the king nor the rook can castle again.
+    state.first_move = state.first_move.and_not(king.or(rook));
 }
 
 pub fn add_castling_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
     let moving_team = board.state.team_to_move();
     let piece = piece_index as u8;
 
-    for king in board.state.pieces[piece_index].and(moving_team).and(board.state.first_move).iter() {
+    let unmoved_kings = board.state.pieces[piece_index].and(moving_team).and(board.state.first_move);
+    if unmoved_kings.empty() {
+        return;
+    }
+
+    // Castling needs a rook to castle with, wherever the game registered it.
+    let Some(Castling { rook: rook_index, .. }) = board.game.castling else {
+        return;
+    };
+
+    for king in unmoved_kings.iter() {
         let pos = king as u16;
 
-        for rook in board.state.pieces[ROOK].and(moving_team).and(board.state.first_move).iter() {
+        for rook in board.state.pieces[rook_index].and(moving_team).and(board.state.first_move).iter() {
             let between_squares = BitBoard::between(king as usize, rook as usize);
             
             // Can't castle if other pieces are in the way.
@@ -50,14 +63,8 @@ pub fn add_castling_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>,
             let king_dest = if rook > king { king + 2 } else { king - 2 };
             let between_dest_squares = BitBoard::between_inclusive(king as usize, king_dest as usize);
 
-            // We'll need the capture mask of the opp team
-            board.state.moving_team = board.state.moving_team.next();
-            let attacks = board.attacks(between_dest_squares);
-            board.state.moving_team = board.state.moving_team.next();
-
-
             // We can't castle through check or while in check, so we'll have to check if that's the case.
-            if attacks {
+            if board.attacked_by(between_dest_squares, board.state.moving_team.next()) {
                 continue;
             }
 
@@ -82,4 +89,45 @@ impl LeaperMoves for KingMoves {
         let moves = vertical.or(right).or(left).and_not(pos);
         moves   
     }
-}
\ No newline at end of file
+}
+
+/// A king, which moves like a `KingMoves` leaper and can castle with unmoved rooks.
+pub struct King;
+
+impl<T: BitInt, const N: usize> PieceRules<T, N> for King {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        Leaper(KingMoves).process(game, piece_index);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        Leaper(KingMoves).add_actions(board, actions, piece_index);
+        add_castling_actions(board, actions, piece_index);
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        Leaper(KingMoves).attacks(board, piece_index, mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(&Leaper(KingMoves), board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(&Leaper(KingMoves), game, piece_index, team, square as usize)
+    }
+
+    fn make_move(&self, board: &mut Board<T, N>, action: Action) {
+        match action.info {
+            0 => make_chess_move(&mut board.state, action),
+            _ => make_castling_move(&mut board.state, action)
+        }
+    }
+
+    /// Castling moves onto the king's own rook, which it doesn't capture.
+    fn captured_square(&self, _board: &Board<T, N>, action: Action) -> Option<u16> {
+        match action.info {
+            0 => Some(action.to),
+            _ => None
+        }
+    }
+}
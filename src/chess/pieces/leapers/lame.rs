@@ -0,0 +1,209 @@
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::{compound::{mover_attackers_to, Mover}, sliders::{leap, rider::symmetries}}, game::{action::Action, piece::PieceRules, Board, Game, Team}};
+
+pub trait LameLeaperMoves {
+    /// Each leap from `pos`, as its legs (the squares which must be empty to make it) and its target.
+    /// Leaps off the board should still be listed with empty targets, so every square has the same number of leaps.
+    fn lame_leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> Vec<(BitBoard<T>, BitBoard<T>)>;
+}
+
+/// Follows `steps` from `pos`, returning every square stepped on before the last as the legs, and the last as the target.
+pub fn lame_leap<T: BitInt>(pos: BitBoard<T>, edges: &Edges<T>, steps: &[(i16, i16)]) -> (BitBoard<T>, BitBoard<T>) {
+    let mut legs = BitBoard::default();
+    let mut square = pos;
+
+    for (index, &step) in steps.iter().enumerate() {
+        square = leap(square, edges, step);
+        if index + 1 < steps.len() {
+            legs = legs.or(square);
+        }
+    }
+
+    (legs, square)
+}
+
+/// The unit step along the longer side of a leap, which orthogonal legs are taken along.
+fn orthogonal_step((x, y): (i16, i16)) -> (i16, i16) {
+    if y.abs() > x.abs() { (0, y.signum()) } else { (x.signum(), 0) }
+}
+
+/// The Xiangqi (and Janggi) horse: a knight which steps orthogonally first, and is blocked there.
+pub struct HorseMoves;
+
+impl LameLeaperMoves for HorseMoves {
+    fn lame_leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> Vec<(BitBoard<T>, BitBoard<T>)> {
+        symmetries((1, 2)).into_iter()
+            .map(|(x, y)| {
+                let leg = orthogonal_step((x, y));
+                lame_leap(pos, edges, &[ leg, (x - leg.0, y - leg.1) ])
+            })
+            .collect()
+    }
+}
+
+/// The Xiangqi elephant: leaps two squares diagonally, blocked by the square in between.
+/// Not crossing the river is up to the game's rules.
+pub struct ElephantMoves;
+
+impl LameLeaperMoves for ElephantMoves {
+    fn lame_leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> Vec<(BitBoard<T>, BitBoard<T>)> {
+        symmetries((2, 2)).into_iter()
+            .map(|(x, y)| lame_leap(pos, edges, &[ (x / 2, y / 2), (x / 2, y / 2) ]))
+            .collect()
+    }
+}
+
+/// The Janggi elephant: one orthogonal step and two diagonal steps outward, blocked on either of the first two.
+pub struct JanggiElephantMoves;
+
+impl LameLeaperMoves for JanggiElephantMoves {
+    fn lame_leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> Vec<(BitBoard<T>, BitBoard<T>)> {
+        symmetries((2, 3)).into_iter()
+            .map(|(x, y)| {
+                let leg = orthogonal_step((x, y));
+                let diagonal = (x.signum(), y.signum());
+                lame_leap(pos, edges, &[ leg, diagonal, diagonal ])
+            })
+            .collect()
+    }
+}
+
+/// A leaper whose leaps can be blocked.
+///
+/// Its lookup stores each leap in two slots: `lookup[piece_index][2 * leap]` holds the legs of the leap,
+/// and `lookup[piece_index][2 * leap + 1]` its target.
+pub struct LameLeaper<S : LameLeaperMoves>(pub S);
+
+impl<S : LameLeaperMoves> LameLeaper<S> {
+    pub fn process<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, piece_index: usize) {
+        let edges = game.edges[0];
+        let bounds = game.bounds;
+        game.lookup[piece_index] = vec![];
+
+        for index in 0..(bounds.rows * bounds.cols) {
+            let leaps = self.0.lame_leaps(BitBoard::index(index), &edges);
+
+            while game.lookup[piece_index].len() < leaps.len() * 2 {
+                game.lookup[piece_index].push(vec![]);
+            }
+
+            for (leap_ind, (legs, target)) in leaps.into_iter().enumerate() {
+                game.lookup[piece_index][2 * leap_ind].push(legs);
+                game.lookup[piece_index][2 * leap_ind + 1].push(target);
+            }
+        }
+    }
+
+    /// Every target reachable from `pos` with `blockers`, including squares of the piece's own team.
+    pub fn list_moves<T: BitInt, const N: usize>(game: &Game<T, N>, piece_index: usize, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        let lookup = &game.lookup[piece_index];
+        let mut moves = BitBoard::default();
+
+        for leap in lookup.chunks_exact(2) {
+            if leap[0][pos].and(blockers).empty() {
+                moves = moves.or(leap[1][pos]);
+            }
+        }
+
+        moves
+    }
+
+    pub fn attacks<T: BitInt, const N: usize>(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let blockers = board.state.black.or(board.state.white);
+
+        board.state.pieces[piece_index]
+            .and(board.state.team_to_move())
+            .iter()
+            .any(|pos| LameLeaper::<S>::list_moves(board.game, piece_index, pos as usize, blockers).and(mask).set())
+    }
+
+    pub fn add_actions<T: BitInt, const N: usize>(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let team = board.state.team_to_move();
+        let blockers = board.state.black.or(board.state.white);
+        let piece = piece_index as u8;
+
+        for pos in board.state.pieces[piece_index].and(team).iter() {
+            let moves = LameLeaper::<S>::list_moves(board.game, piece_index, pos as usize, blockers).and_not(team);
+
+            for to in moves.iter() {
+                actions.push(Action::from(pos as u16, to as u16, piece));
+            }
+        }
+    }
+}
+
+impl<T: BitInt, const N: usize, S : LameLeaperMoves + Send + Sync> PieceRules<T, N> for LameLeaper<S> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        LameLeaper::process(self, game, piece_index);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        LameLeaper::add_actions(self, board, actions, piece_index);
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        LameLeaper::attacks(self, board, piece_index, mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+impl<T: BitInt, const N: usize, S : LameLeaperMoves + Send + Sync> Mover<T, N> for LameLeaper<S> {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize) {
+        LameLeaper::process(self, game, lookup_index);
+    }
+
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, _team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        LameLeaper::<S>::list_moves(game, lookup_index, pos, blockers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{pieces::testing::{chess_variant, piece_moves}, BISHOP, KNIGHT, QUEEN}, game::{piece::Piece, Game}};
+
+    use super::{ElephantMoves, HorseMoves, JanggiElephantMoves, LameLeaper};
+
+    fn lame_chess() -> Game<u64, 6> {
+        chess_variant(vec![
+            (KNIGHT, Piece::new("knight", 'n', LameLeaper(HorseMoves))),
+            (BISHOP, Piece::new("bishop", 'b', LameLeaper(ElephantMoves))),
+            (QUEEN, Piece::new("queen", 'q', LameLeaper(JanggiElephantMoves)))
+        ])
+    }
+
+    #[test]
+    fn lame_leaps_are_blocked() {
+        let game = lame_chess();
+
+        // The horse's leg on d5 is blocked, so it can't reach c6 or e6.
+        let mut board = game.load("4k3/8/8/3p4/3N4/8/8/4K3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, 1), vec![ "d4b3", "d4b5", "d4c2", "d4e2", "d4f3", "d4f5" ]);
+
+        // The elephant's leg on e5 is blocked, so it can't reach f6.
+        let mut board = game.load("4k3/8/8/4p3/3B4/8/8/4K3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, 2), vec![ "d4b2", "d4b6", "d4f2" ]);
+
+        // The Janggi elephant is blocked both on its orthogonal step (d5) and its first diagonal step (b3).
+        let mut board = game.load("4k3/8/8/3p4/3Q4/1p6/8/4K3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, 4), vec![ "d4a6", "d4b1", "d4f1", "d4g2", "d4g6" ]);
+    }
+
+    #[test]
+    fn lame_leaps_give_check() {
+        let game = lame_chess();
+
+        // The horse on d6 checks e8 through its open leg on d7, so only the king can move.
+        let mut board = game.load("4k3/7p/3N4/8/8/8/8/4K3 b - - 0 1");
+        assert!(board.legals().iter().all(|action| action.piece == 5));
+
+        // With the leg on d7 blocked, the horse doesn't give check, so the h7 pawn can move.
+        let mut board = game.load("4k3/3p3p/3N4/8/8/8/8/4K3 b - - 0 1");
+        assert!(board.legals().iter().any(|&action| board.display_uci_action(action) == "h7h6"));
+    }
+}
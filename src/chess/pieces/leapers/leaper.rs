@@ -1,28 +1,49 @@
-use crate::{bitboard::{BitBoard, BitInt, Edges}, game::{action::Action, Board, Game}};
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::compound::{mover_attackers_to, Mover}, game::{action::Action, piece::PieceRules, Board, Game, Team}};
 
 pub trait LeaperMoves {
     fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T>;
+
+    /// Whether the leaps depend on the team, like a shogi general's.
+    /// If so, `leaps` gives white's leaps, and black's are mirrored from them.
+    fn directional(&self) -> bool {
+        false
+    }
 }
 
 pub struct Leaper<S : LeaperMoves>(pub S);
 
 impl<S : LeaperMoves> Leaper<S> {
     pub fn process<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, piece_index: usize) {
+        self.process_team(game, piece_index, Team::White);
+
+        let black = game.add_team_lookup(piece_index, self.0.directional());
+        if black != piece_index {
+            self.process_team(game, black, Team::Black);
+        }
+    }
+
+    /// Fills `lookup_index` with the leaps of `team`, mirroring white's leaps for black.
+    fn process_team<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, lookup_index: usize, team: Team) {
         let edges = game.edges[0];
-        game.lookup[piece_index] = vec![ vec![] ];
+        let bounds = game.bounds;
+        game.lookup[lookup_index] = vec![ vec![] ];
 
-        for index in 0..64 {
+        for index in 0..(bounds.rows * bounds.cols) {
             let leaper = BitBoard::index(index);
-            let moves = self.0.leaps(leaper, &edges);
+            let moves = match team {
+                Team::White => self.0.leaps(leaper, &edges),
+                Team::Black => self.0.leaps(leaper.flip_vertical(bounds), &edges).flip_vertical(bounds)
+            };
 
-            game.lookup[piece_index][0].push(moves);
+            game.lookup[lookup_index][0].push(moves);
         }
     }
     
     pub fn attacks<T: BitInt, const N: usize>(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
         let moving_team = board.state.team_to_move();
+        let lookup_index = board.game.team_lookup(piece_index, board.state.moving_team);
         for leaper in board.state.pieces[piece_index].and(moving_team).iter() {
-            let attacks = board.game.lookup[piece_index][0][leaper as usize];
+            let attacks = board.game.lookup[lookup_index][0][leaper as usize];
             if attacks.and(mask).set() {
                 return true;
             }
@@ -34,14 +55,47 @@ impl<S : LeaperMoves> Leaper<S> {
 
     pub fn add_actions<T: BitInt, const N: usize>(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
         let moving_team = board.state.team_to_move();
+        let lookup_index = board.game.team_lookup(piece_index, board.state.moving_team);
 
         let piece = piece_index as u8;
         for leaper in board.state.pieces[piece_index].and(moving_team).iter() {
             let pos = leaper as u16;
-            let moves = board.game.lookup[piece_index][0][leaper as usize].and_not(moving_team);
+            let moves = board.game.lookup[lookup_index][0][leaper as usize].and_not(moving_team);
             for movement in moves.iter() {
                 actions.push(Action::from(pos, movement as u16, piece))
             }
         }
     }
-}
\ No newline at end of file
+}
+
+impl<T: BitInt, const N: usize, S : LeaperMoves + Send + Sync> PieceRules<T, N> for Leaper<S> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        Leaper::process(self, game, piece_index);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        Leaper::add_actions(self, board, actions, piece_index);
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        Leaper::attacks(self, board, piece_index, mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+impl<T: BitInt, const N: usize, S : LeaperMoves + Send + Sync> Mover<T, N> for Leaper<S> {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize) {
+        Leaper::process(self, game, lookup_index);
+    }
+
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, _blockers: BitBoard<T>) -> BitBoard<T> {
+        game.lookup[game.team_lookup(lookup_index, team)][0][pos]
+    }
+}
@@ -1,3 +1,6 @@
 pub mod knight;
 pub mod king;
-pub mod leaper;
\ No newline at end of file
+pub mod leaper;
+pub mod lame;
+pub mod general;
+pub mod shogi;
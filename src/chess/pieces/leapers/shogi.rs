@@ -0,0 +1,31 @@
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::sliders::leap};
+
+use super::leaper::LeaperMoves;
+
+/// The shogi pawn: steps straight forward, and captures the same way.
+#[derive(Copy, Clone)]
+pub struct ShogiPawnMoves;
+
+impl LeaperMoves for ShogiPawnMoves {
+    fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T> {
+        leap(pos, edges, (0, 1))
+    }
+
+    fn directional(&self) -> bool {
+        true
+    }
+}
+
+/// The shogi knight: only leaps to the two squares two ahead and one to the side.
+#[derive(Copy, Clone)]
+pub struct ShogiKnightMoves;
+
+impl LeaperMoves for ShogiKnightMoves {
+    fn leaps<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> BitBoard<T> {
+        leap(pos, edges, (1, 2)).or(leap(pos, edges, (-1, 2)))
+    }
+
+    fn directional(&self) -> bool {
+        true
+    }
+}
@@ -1,4 +1,9 @@
 pub mod pawn;
 pub mod leapers;
 pub mod sliders;
+pub mod betza;
+pub mod compound;
+pub mod divergent;
 
+#[cfg(test)]
+pub mod testing;
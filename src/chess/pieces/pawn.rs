@@ -1,4 +1,4 @@
-use crate::{bitboard::{BitBoard, BitInt}, game::{action::{index_to_square, make_chess_move, Action, ActionRecord}, Board, BoardState, Team}};
+use crate::{bitboard::{BitBoard, BitInt}, game::{action::{index_to_square, make_chess_move, Action, ActionRecord}, piece::PieceRules, promotion::make_promotion_move, Board, BoardState, Game, Team}};
 
 #[inline(always)]
 fn list_white_pawn_captures<T: BitInt, const N: usize>(board: &mut Board<T, N>, piece_index: usize) -> BitBoard<T> {
@@ -24,30 +24,6 @@ fn list_black_pawn_captures<T: BitInt, const N: usize>(board: &mut Board<T, N>,
     left_captures.or(right_captures)
 }
 
-#[inline(always)]
-fn add_white_action<T: BitInt, const N: usize>(board: &mut Board<T, N>, actions: &mut Vec<Action>, action: Action) {
-    if action.to <= (board.game.bounds.rows * (board.game.bounds.cols - 1)) - 1 {
-        actions.push(action);
-    } else {
-        actions.push(action.with_info(3));
-        actions.push(action.with_info(4));
-        actions.push(action.with_info(5));
-        actions.push(action.with_info(6));
-    }
-}
-
-#[inline(always)]
-fn add_black_action<T: BitInt, const N: usize>(board: &mut Board<T, N>, actions: &mut Vec<Action>, action: Action) {
-    if action.to >= board.game.bounds.rows {
-        actions.push(action);
-    } else {
-        actions.push(action.with_info(3));
-        actions.push(action.with_info(4));
-        actions.push(action.with_info(5));
-        actions.push(action.with_info(6));
-    }
-}
-
 #[inline(always)]
 fn add_white_pawn_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
     let edges = board.game.edges[0];
@@ -77,19 +53,19 @@ fn add_white_pawn_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, ac
 
     for movement in moves.iter() {
         let movement = movement as u16;
-        add_white_action(board, actions, Action::from(movement - 8, movement, piece));
+        actions.push(Action::from(movement - 8, movement, piece));
     }
     for movement in first_moves.iter() {
         let movement = movement as u16;
-        add_white_action(board, actions, Action::from(movement - 16, movement, piece));
+        actions.push(Action::from(movement - 16, movement, piece));
     }
     for movement in left_captures.iter() {
         let movement = movement as u16;
-        add_white_action(board, actions, Action::from(movement - 8 + 1, movement, piece));
+        actions.push(Action::from(movement - 8 + 1, movement, piece));
     }
     for movement in right_captures.iter() {
         let movement = movement as u16;
-        add_white_action(board, actions, Action::from(movement - 8 - 1, movement, piece));
+        actions.push(Action::from(movement - 8 - 1, movement, piece));
     }
 
     if let Some(ActionRecord::Action(last_move)) = board.history.last() {
@@ -103,11 +79,11 @@ fn add_white_pawn_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, ac
                     let capture = last_move.from - 8;
                     let target = BitBoard::<T>::index(capture.into());
                     if possible_left_captures.and(target).set() {
-                        add_white_action(board, actions, Action::from(capture - 8 + 1, capture, piece).with_info(1));
+                        actions.push(Action::from(capture - 8 + 1, capture, piece).with_info(1));
                     }
 
                     if possible_right_captures.and(target).set() {
-                        add_white_action(board, actions, Action::from(capture - 8 - 1, capture, piece).with_info(1));
+                        actions.push(Action::from(capture - 8 - 1, capture, piece).with_info(1));
                     }
                 }
             }
@@ -144,19 +120,19 @@ fn add_black_pawn_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, ac
 
     for movement in moves.iter() {
         let movement = movement as u16;
-        add_black_action(board, actions, Action::from(movement + 8, movement, piece));
+        actions.push(Action::from(movement + 8, movement, piece));
     }
     for movement in first_moves.iter() {
         let movement = movement as u16;
-        add_black_action(board, actions, Action::from(movement + 16, movement, piece));
+        actions.push(Action::from(movement + 16, movement, piece));
     }
     for movement in left_captures.iter() {
         let movement = movement as u16;
-        add_black_action(board, actions, Action::from(movement + 8 + 1, movement, piece));
+        actions.push(Action::from(movement + 8 + 1, movement, piece));
     }
     for movement in right_captures.iter() {
         let movement = movement as u16;
-        add_black_action(board, actions, Action::from(movement + 8 - 1, movement, piece));
+        actions.push(Action::from(movement + 8 - 1, movement, piece));
     }
 
     if let Some(ActionRecord::Action(last_move)) = board.history.last() {
@@ -170,11 +146,11 @@ fn add_black_pawn_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, ac
                     let capture = last_move.from + 8;
                     let target = BitBoard::<T>::index(capture.into());
                     if possible_left_captures.and(target).set() {
-                        add_black_action(board, actions, Action::from(capture + 8 + 1, capture, piece).with_info(1));
+                        actions.push(Action::from(capture + 8 + 1, capture, piece).with_info(1));
                     }
 
                     if possible_right_captures.and(target).set() {
-                        add_black_action(board, actions, Action::from(capture + 8 - 1, capture, piece).with_info(1));
+                        actions.push(Action::from(capture + 8 - 1, capture, piece).with_info(1));
                     }
                 }
             }
@@ -182,17 +158,21 @@ fn add_black_pawn_actions<T: BitInt, const N: usize>(board: &mut Board<T, N>, ac
     }
 }
 
+/// The square of the pawn `team` takes en passant by moving to `to`, which is one square ahead of it.
+#[inline(always)]
+pub fn en_passant_victim(team: Team, to: u16) -> u16 {
+    match team {
+        Team::White => to - 8,
+        Team::Black => to + 8
+    }
+}
+
 pub fn make_en_passant_move<T: BitInt, const N: usize>(state: &mut BoardState<T, N>, action: Action) {
     let team = state.moving_team;
     let from = BitBoard::index(action.from);
     let to = BitBoard::index(action.to);
 
-    // The taken pawn is one square ahead of the en passant destination.
-    let taken_pos = match team { 
-        Team::White => action.to - 8,
-        Team::Black => action.to + 8 
-    };
-    let taken = BitBoard::index(taken_pos);
+    let taken = BitBoard::index(en_passant_victim(team, action.to));
 
     let piece_index = action.piece as usize;
 
@@ -208,66 +188,9 @@ pub fn make_en_passant_move<T: BitInt, const N: usize>(state: &mut BoardState<T,
     }
 
     state.pieces[piece_index as usize] = state.pieces[piece_index as usize].xor(from).xor(taken).or(to);
-    state.first_move = state.first_move.xor(from).xor(taken);
-}
-
-pub fn make_promotion_move<T: BitInt, const N: usize>(state: &mut BoardState<T, N>, action: Action) {
-    let piece_index = action.piece as usize;
-    let victim_index = state.piece_at(action.to);
-    let promoted_piece_type = action.info - 2;
-
-    let white = state.white;
-    let black = state.black;
-
-    let pawns = state.pieces[piece_index as usize];
-
-    let from = BitBoard::index(action.from);
-    let to = BitBoard::index(action.to);
-
-    let team = state.moving_team;
-
-    if let Some(piece_type) = victim_index {
-        // Remove the captured piece type from its bitboard
-        let piece = state.pieces[piece_type as usize];
-        let same_piece_type = piece_type == piece_index;
-        if !same_piece_type {
-            state.pieces[piece_type as usize] = piece.xor(to);
-        }
-
-        match team {
-            Team::White => {
-                state.black = black.xor(to);
-            }
-            Team::Black => {
-                state.white = white.xor(to);
-            }
-        }
-    }
-
-    // Remove the pawn
-    state.pieces[piece_index as usize] = pawns.xor(from);
-
-    // Add the new piece where the pawn left.
-    state.pieces[promoted_piece_type as usize] = state.pieces[promoted_piece_type as usize].or(to);
-
-    // Update the moved piece's team bitboard
-    match team {
-        Team::White => {
-            state.white = white.xor(from).or(to);
-        }
-        Team::Black => {
-            state.black = black.xor(from).or(to);
-        }
-    }
-
-    let first_move = state.first_move.and_not(from.or(to));
-
-    if first_move != state.first_move {
-        state.first_move = first_move;
-    }
+    state.first_move = state.first_move.and_not(from.or(taken));
 }
 
-
 pub struct Pawn;
 
 impl Pawn {
@@ -295,4 +218,59 @@ impl Pawn {
             Team::Black => list_black_pawn_captures(board, piece_index).and(mask).set()
         }
     }
-}
\ No newline at end of file
+
+    /// Pawns attack diagonally forward, so `team`'s attackers are diagonally behind `square` from its side.
+    pub fn attackers_to<T: BitInt, const N: usize>(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        let edges = board.game.edges[0];
+        let behind = match team {
+            Team::White => BitBoard::index(square).down(1),
+            Team::Black => BitBoard::index(square).up(1)
+        };
+
+        behind.try_left(&edges, 1)
+            .or(behind.try_right(&edges, 1))
+            .and(board.state.pieces[piece_index])
+            .and(board.state.team(team))
+            .and(occupancy)
+    }
+}
+
+impl<T: BitInt, const N: usize> PieceRules<T, N> for Pawn {
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        Pawn::add_actions(self, board, actions, piece_index);
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        Pawn::attacks(self, board, piece_index, mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Pawn::attackers_to(self, board, piece_index, square, team, occupancy)
+    }
+
+    /// Pawns only attack diagonally forward.
+    fn reach(&self, game: &Game<T, N>, _piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        let edges = game.edges[0];
+        let ahead = match team {
+            Team::White => BitBoard::index(square).up(1),
+            Team::Black => BitBoard::index(square).down(1)
+        };
+
+        ahead.try_left(&edges, 1).or(ahead.try_right(&edges, 1))
+    }
+
+    fn make_move(&self, board: &mut Board<T, N>, action: Action) {
+        match action.info {
+            0 => make_chess_move(&mut board.state, action),
+            1 => make_en_passant_move(&mut board.state, action),
+            _ => make_promotion_move(&mut board.state, action)
+        }
+    }
+
+    fn captured_square(&self, board: &Board<T, N>, action: Action) -> Option<u16> {
+        match action.info {
+            1 => Some(en_passant_victim(board.state.moving_team, action.to)),
+            _ => Some(action.to)
+        }
+    }
+}
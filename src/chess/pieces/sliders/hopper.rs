@@ -0,0 +1,274 @@
+use crate::{bitboard::{BitBoard, BitInt}, chess::pieces::compound::{mover_attackers_to, Mover}, game::{action::Action, piece::PieceRules, Board, Game, Team}};
+
+use super::{closest, magics::{generate_magics, magics_feasible, Magic}, ray_attacks, slider::{Slider, SliderMoves}};
+
+/// How a hopper uses the first piece along a ray (its screen, or hurdle).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hop {
+    /// Reaches every square behind the screen, up to and including the next piece (like a Xiangqi cannon's captures).
+    Cannon,
+    /// Reaches only the square directly behind the hurdle (like a grasshopper).
+    Grasshopper
+}
+
+/// Every square a hopper reaches from `pos`, over the rays of a `Slider` processed into `lookup_index`.
+///
+/// Like `Slider::list_moves`, this includes squares of the hopper's own team.
+pub fn hop_targets<T: BitInt, const N: usize>(
+    game: &Game<T, N>,
+    lookup_index: usize,
+    pos: usize,
+    blockers: BitBoard<T>,
+    hop: Hop
+) -> BitBoard<T> {
+    let rays = game.lookup[lookup_index].len() - 1;
+    let mut targets = BitBoard::default();
+
+    for dir in 0..rays {
+        let ray = game.lookup[lookup_index][dir][pos];
+        let screens = ray.and(blockers);
+        if screens.empty() {
+            continue;
+        }
+
+        let screen = closest(pos, screens);
+        let beyond = ray.and(game.lookup[lookup_index][dir][screen]);
+        if beyond.empty() {
+            continue;
+        }
+
+        targets = targets.or(match hop {
+            Hop::Cannon => ray_attacks(game, lookup_index, screen, dir, beyond, blockers),
+            Hop::Grasshopper => BitBoard::index(closest(screen, beyond) as u16)
+        });
+    }
+
+    targets
+}
+
+/// Generates magics into `lookup_index` for hopping over the rays in `rays_index`.
+///
+/// A hop depends on the first two pieces along a ray rather than only the first,
+/// but never on the ray's farthest square, so the relevant blockers are the same as a slider's.
+pub fn process_hop_magics<T: BitInt, const N: usize>(game: &mut Game<T, N>, rays_index: usize, lookup_index: usize, hop: Hop) {
+    generate_magics(game, rays_index, lookup_index, |game, pos, blockers| {
+        hop_targets(game, rays_index, pos, blockers, hop)
+    });
+}
+
+/// A piece which hops over screens along the rays of `S`.
+///
+/// A `Hop::Cannon` moves like its slider, but only captures by hopping (a Xiangqi cannon with `RookMoves`),
+/// while a `Hop::Grasshopper` only moves and captures by hopping.
+/// The rays are stored at the piece's index (or black's slot of it, for directional rays),
+/// and magics in extra lookup slots when `magics_feasible` allows it.
+pub struct Hopper<S : SliderMoves> {
+    pub moves: S,
+    pub hop: Hop,
+    /// Indexed by team; the lookup slots for magics of slides and hops, if generated.
+    magics: Option<[(usize, usize); 2]>
+}
+
+impl<S : SliderMoves> Hopper<S> {
+    pub fn new(moves: S, hop: Hop) -> Hopper<S> {
+        Hopper { moves, hop, magics: None }
+    }
+
+    fn slides<T: BitInt, const N: usize>(&self, game: &Game<T, N>, piece_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        match self.magics {
+            Some(magics) => Magic::<S>::list_moves(game, magics[team.index()].0, pos, blockers),
+            None => Slider::<S>::list_moves(game, game.team_lookup(piece_index, team), pos, blockers)
+        }
+    }
+
+    fn hops<T: BitInt, const N: usize>(&self, game: &Game<T, N>, piece_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        match self.magics {
+            Some(magics) => Magic::<S>::list_moves(game, magics[team.index()].1, pos, blockers),
+            None => hop_targets(game, game.team_lookup(piece_index, team), pos, blockers, self.hop)
+        }
+    }
+
+    fn process_magics<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, rays_index: usize) -> (usize, usize) {
+        let slides = game.add_lookup();
+        generate_magics(game, rays_index, slides, |game, pos, blockers| {
+            Slider::<S>::list_moves(game, rays_index, pos, blockers)
+        });
+
+        let hops = game.add_lookup();
+        process_hop_magics(game, rays_index, hops, self.hop);
+
+        (slides, hops)
+    }
+}
+
+impl<T: BitInt, const N: usize, S : SliderMoves + Send + Sync> PieceRules<T, N> for Hopper<S> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        Slider(self.moves.clone()).process(game, piece_index);
+        self.magics = None;
+
+        let black = game.team_lookup(piece_index, Team::Black);
+        if magics_feasible(game, piece_index) && magics_feasible(game, black) {
+            let white_magics = self.process_magics(game, piece_index);
+            let black_magics = if black == piece_index { white_magics } else { self.process_magics(game, black) };
+
+            self.magics = Some([ white_magics, black_magics ]);
+        }
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let moving_team = board.state.moving_team;
+        let team = board.state.team_to_move();
+        let enemies = board.state.opposite_team();
+        let blockers = team.or(enemies);
+        let piece = piece_index as u8;
+
+        for pos in board.state.pieces[piece_index].and(team).iter() {
+            let hops = self.hops(board.game, piece_index, moving_team, pos as usize, blockers);
+            let moves = match self.hop {
+                Hop::Cannon => self.slides(board.game, piece_index, moving_team, pos as usize, blockers)
+                    .and_not(blockers)
+                    .or(hops.and(enemies)),
+                Hop::Grasshopper => hops.and_not(team)
+            };
+
+            for to in moves.iter() {
+                actions.push(Action::from(pos as u16, to as u16, piece));
+            }
+        }
+    }
+
+    /// A cannon attacks every square of its hops, since a piece placed on any of them would be the one captured.
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let blockers = board.state.black.or(board.state.white);
+
+        board.state.pieces[piece_index]
+            .and(board.state.team_to_move())
+            .iter()
+            .any(|pos| self.hops(board.game, piece_index, board.state.moving_team, pos as usize, blockers).and(mask).set())
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Mover::lines(self, board.game, piece_index, team, square as usize, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+/// As a mover, a hopper reaches only its hops, so a `Divergent` piece can slide quietly and capture by hopping.
+impl<T: BitInt, const N: usize, S : SliderMoves + Send + Sync> Mover<T, N> for Hopper<S> {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize) {
+        PieceRules::process(self, game, lookup_index);
+    }
+
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        self.hops(game, lookup_index, team, pos, blockers)
+    }
+
+    /// A hopper's lines go on to the second piece along them, since the first can be its screen.
+    fn lines(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, occupancy: BitBoard<T>) -> BitBoard<T> {
+        let slides = self.slides(game, lookup_index, team.next(), pos, occupancy);
+        let screens = slides.and(occupancy);
+
+        slides.or(self.slides(game, lookup_index, team.next(), pos, occupancy.and_not(screens)))
+    }
+
+    /// A hopper only lands along its rays, but needs a screen to hop over, so it reaches its slides on an empty board.
+    fn reach(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize) -> BitBoard<T> {
+        self.slides(game, lookup_index, team, pos, BitBoard::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::Bounds, chess::{pieces::{betza::Betza, sliders::{queen::QueenMoves, rook::RookMoves}, testing::{chess_variant, chess_with, piece_moves, square, squares}}, QUEEN}, game::{piece::{Piece, PieceRules}, Team}};
+
+    use super::{hop_targets, Hop, Hopper};
+
+    #[test]
+    fn cannon_moves() {
+        let game = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Hopper::new(RookMoves, Hop::Cannon))) ]);
+
+        // The cannon slides to d2 and d3, hops over d4 to capture on d7, and can't capture the adjacent c1 knight.
+        let mut board = game.load("4k3/3p4/8/8/3P4/8/8/2nQK3 w - - 0 1");
+        assert_eq!(piece_moves(&mut board, QUEEN as u8), vec![ "d1d2", "d1d3", "d1d7" ]);
+
+        // The cannon checks over the d5 screen, so the h2 knight can't move and the king can't step along the file.
+        let mut board = game.load("3k4/8/8/3p4/8/8/7n/3QK3 b - - 0 1");
+        let legals: Vec<String> = board.legals().into_iter()
+            .map(|action| board.display_uci_action(action))
+            .collect();
+        assert!(legals.iter().all(|action| !action.starts_with("h2")));
+        assert!(legals.contains(&"d8c8".to_string()));
+        assert!(!legals.contains(&"d8d7".to_string()));
+    }
+
+    #[test]
+    fn grasshopper_moves() {
+        let game = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Hopper::new(QueenMoves, Hop::Grasshopper))) ]);
+
+        // Grasshoppers land directly behind hurdles: over d2 onto the d3 pawn, and over e2 onto f3.
+        let mut board = game.load("4k3/8/8/8/8/3p4/3PP3/3Q3K w - - 0 1");
+        assert_eq!(piece_moves(&mut board, QUEEN as u8), vec![ "d1d3", "d1f3" ]);
+
+        // Without a hurdle, a grasshopper can't move.
+        let mut board = game.load("4k3/8/8/8/8/8/8/3Q3K w - - 0 1");
+        assert!(piece_moves(&mut board, QUEEN as u8).is_empty());
+    }
+
+    #[test]
+    fn hop_magics() {
+        let mut game = chess_with(Bounds::new(8, 8), vec![]);
+
+        // From d4, the d6 screen leaves d7 and the d8 piece, the b4 screen only the a4 piece,
+        // and the g4 and d2 screens only the empty squares behind them.
+        let pos = square(8, "d4");
+        let blockers = squares(8, &[ "d6", "d8", "b4", "a4", "g4", "d2" ]);
+        let cannon = squares(8, &[ "d7", "d8", "a4", "h4", "d1" ]);
+        let grasshopper = squares(8, &[ "d7", "a4", "h4", "d1" ]);
+
+        for (hop, targets) in [ (Hop::Cannon, cannon), (Hop::Grasshopper, grasshopper) ] {
+            let mut hopper = Hopper::new(RookMoves, hop);
+            hopper.process(&mut game, 3);
+            assert!(hopper.magics.is_some());
+
+            assert_eq!(hopper.hops(&game, 3, Team::White, pos, blockers), targets);
+            assert_eq!(hop_targets(&game, 3, pos, blockers, hop), targets);
+        }
+
+        // Boards smaller than the integer only get magics for the squares they have.
+        // On 6x6, from c3, screens on the a3 and c1 edges have nothing behind them.
+        let mut game = chess_with(Bounds::new(6, 6), vec![]);
+        let pos = square(6, "c3");
+        let blockers = squares(6, &[ "c4", "a3", "e3", "f3", "c1" ]);
+        let cannon = squares(6, &[ "c5", "c6", "f3" ]);
+        let grasshopper = squares(6, &[ "c5", "f3" ]);
+
+        for (hop, targets) in [ (Hop::Cannon, cannon), (Hop::Grasshopper, grasshopper) ] {
+            let mut hopper = Hopper::new(RookMoves, hop);
+            hopper.process(&mut game, 3);
+            assert!(hopper.magics.is_some());
+
+            assert_eq!(hopper.hops(&game, 3, Team::White, pos, blockers), targets);
+            assert_eq!(hop_targets(&game, 3, pos, blockers, hop), targets);
+        }
+    }
+
+    #[test]
+    fn cannon_betza() {
+        let hopper = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Hopper::new(RookMoves, Hop::Cannon))) ]);
+        let betza = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Betza::new("mRcpR").expect("Valid notation"))) ]);
+
+        // The cannon slides to the empty c4, d3 and e4, captures the d7 rook over d5 and the h4 pawn over f4,
+        // but has nothing to capture behind the b4 and d2 screens.
+        for game in [ hopper, betza ] {
+            let mut board = game.load("3k4/3r4/8/3p4/1p1Q1P1p/8/3P4/4K3 w - - 0 1");
+            assert_eq!(piece_moves(&mut board, QUEEN as u8), vec![ "d4c4", "d4d3", "d4d7", "d4e4", "d4h4" ]);
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use crate::bitboard::{BitBoard, BitInt, Edges};
+
+use super::{repeat, slider::SliderMoves};
+
+/// The shogi lance: slides straight forward.
+#[derive(Copy, Clone)]
+pub struct LanceMoves;
+
+impl SliderMoves for LanceMoves {
+    fn rays<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> Vec<BitBoard<T>> {
+        vec![ repeat(pos, |pos| pos.try_up(edges, 1)) ]
+    }
+
+    fn directional(&self) -> bool {
+        true
+    }
+}
@@ -1,25 +1,26 @@
 
-use crate::{bitboard::{BitBoard, BitInt, Edges}, game::{action::{make_chess_move, Action}, AttackLookup, Board, Game, MagicEntry, PieceMagics}};
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::compound::{mover_attackers_to, Mover}, game::{action::{make_chess_move, Action}, piece::PieceRules, AttackLookup, Board, Game, MagicEntry, PieceMagics, Team}};
 
-use super::{ray_attacks, repeat, slider::{Slider, SliderMoves}};
+use super::{bishop::BishopMoves, ray_attacks, repeat, rook::RookMoves, slider::{Slider, SliderMoves}};
 
-fn try_make_table<T : BitInt, S : SliderMoves, const N: usize>(
-    game: &mut Game<T, N>, 
+fn try_make_table<T : BitInt, const N: usize>(
+    game: &Game<T, N>, 
     entry: MagicEntry<T>,
-    piece_index: usize,
-    index: usize
+    index: usize,
+    list: &impl Fn(&Game<T, N>, usize, BitBoard<T>) -> BitBoard<T>
 ) -> Option<Vec<BitBoard<T>>> {
-    let mut table = vec![ BitBoard::<T>::default(); (1 << (64 - entry.shift)) as usize ];
+    // Empty move sets are valid entries (hoppers often have none), so written slots are tracked separately
+    let mut table: Vec<Option<BitBoard<T>>> = vec![ None; (1 << (64 - entry.shift)) as usize ];
     let mut blockers = BitBoard::<T>::default();
     loop {
-        let moves = Slider::<S>::list_moves(game, piece_index, index, blockers);
+        let moves = list(game, index, blockers);
         let table_entry = &mut table[magic_index(entry, blockers)];
-        if table_entry.empty() {
+        match table_entry {
             // Write to empty slot
-            *table_entry = moves;
-        } else if *table_entry != moves {
+            None => *table_entry = Some(moves),
             // Having two different move sets in the same slot is a hash collision
-            return None;
+            Some(existing) if *existing != moves => return None,
+            _ => {}
         }
 
         blockers.0 = blockers.0.wrapping_sub(&entry.mask.0) & entry.mask.0;
@@ -29,7 +30,7 @@ fn try_make_table<T : BitInt, S : SliderMoves, const N: usize>(
         }
     }
 
-    Some(table)
+    Some(table.into_iter().map(|moves| moves.unwrap_or(BitBoard::default())).collect())
 }
 
 fn magic_index<T : BitInt>(entry: MagicEntry<T>, blockers: BitBoard<T>) -> usize {
@@ -75,57 +76,127 @@ fn filter_friendly<T: BitInt>(moves: BitBoard<T>, team: BitBoard<T>) -> BitBoard
     moves.and_not(team)
 }
 
-#[derive(Copy, Clone)]
-pub struct Magic<S : SliderMoves>(pub S);
+/// The most relevant blockers a square may have for magic lookups to be worth generating.
+pub const MAX_MAGIC_BITS: u32 = 12;
 
-impl <S : SliderMoves> Magic<S> {
-    pub fn process<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, piece_index: usize) {
-        let slider = Slider(self.0);
-        slider.process(game, piece_index);
-        
-        // We need raycasting to check if magics are valid
+/// How many magics are tried for a square before its table gets an extra bit.
+const MAGIC_ATTEMPTS: usize = 10_000;
 
-        let edges = game.edges[0];
+/// The squares whose occupancy changes what `rays` can reach from `pos`.
+///
+/// That's every square of a ray except its farthest one, since nothing lies behind it.
+/// This holds for rays of any leap or range, so riders get magics the same way as rooks and bishops.
+pub fn relevant_blockers<T : BitInt>(rays: &[BitBoard<T>], pos: usize) -> BitBoard<T> {
+    let mut relevant = BitBoard::default();
 
-        let mut lookup: AttackLookup<T> = vec![];
-        let mut magics: PieceMagics<T> = vec![];
+    for &ray in rays {
+        if ray.empty() {
+            continue;
+        }
 
-        for index in 0..64 {
-            let pos = BitBoard::index(index);
-            let rays = self.0.rays(pos, &edges);
-            let mut relevant_blockers = BitBoard::default();
+        let farthest = if BitBoard::index(pos as u16).lt(ray) {
+            ray.bitscan_backward()
+        } else {
+            ray.bitscan_forward()
+        };
+        relevant = relevant.or(ray.and_not(BitBoard::index(farthest as u16)));
+    }
 
-            for ray in rays {
-                for edge in [edges.bottom, edges.left, edges.right, edges.top] {
-                    if ray.and(edge).count() != 1 { continue; }
+    relevant
+}
 
-                    relevant_blockers = relevant_blockers.or(ray.and_not(edge));
-                    break;
-                }  
-            }
-            
-            loop {
-                let magic = fastrand::u64(..) & fastrand::u64(..) & fastrand::u64(..);
-
-                let entry = MagicEntry {
-                    mask: relevant_blockers,
-                    magic: T::from(magic).expect("Must work"),
-                    shift: 64 - relevant_blockers.count() as usize
-                };
-    
-                let table = try_make_table::<T, S, N>(game, entry, piece_index, index as usize);
+/// The relevant blockers of a `Slider` processed into `rays_index`, at `pos`.
+pub fn slider_relevant_blockers<T : BitInt, const N: usize>(game: &Game<T, N>, rays_index: usize, pos: usize) -> BitBoard<T> {
+    let lookup = &game.lookup[rays_index];
+    let rays: Vec<BitBoard<T>> = lookup[..lookup.len() - 1].iter()
+        .map(|ray| ray[pos])
+        .collect();
 
-                if let Some(table) = table {
-                    lookup.push(table);
-                    magics.push(entry);
+    relevant_blockers(&rays, pos)
+}
+
+/// Whether magics can be generated over the rays of a `Slider` processed into `rays_index`:
+/// `T` must fit in the 64 bit magics, and no square may have more than `MAX_MAGIC_BITS` relevant blockers.
+pub fn magics_feasible<T : BitInt, const N: usize>(game: &Game<T, N>, rays_index: usize) -> bool {
+    let squares = (game.bounds.rows * game.bounds.cols) as usize;
+
+    T::zero().count_zeros() <= 64 && (0..squares).all(|pos| {
+        slider_relevant_blockers(game, rays_index, pos).count() <= MAX_MAGIC_BITS
+    })
+}
+
+/// Generates magics into `lookup_index`, hashing whatever `list` finds from each square
+/// for every configuration of the relevant blockers of the rays in `rays_index`.
+pub fn generate_magics<T : BitInt, const N: usize>(
+    game: &mut Game<T, N>,
+    rays_index: usize,
+    lookup_index: usize,
+    list: impl Fn(&Game<T, N>, usize, BitBoard<T>) -> BitBoard<T>
+) {
+    let mut lookup: AttackLookup<T> = vec![];
+    let mut magics: PieceMagics<T> = vec![];
+    let squares = (game.bounds.rows * game.bounds.cols) as usize;
+
+    for index in 0..squares {
+        let relevant_blockers = slider_relevant_blockers(game, rays_index, index);
+        // A shift of 64 would overflow, and with no relevant blockers every hash is 0 anyway.
+        let mut bits = relevant_blockers.count().max(1) as usize;
+        let mut attempts = 0;
 
-                    break;
-                }
+        loop {
+            let magic = fastrand::u64(..) & fastrand::u64(..) & fastrand::u64(..);
+
+            let entry = MagicEntry {
+                mask: relevant_blockers,
+                magic: T::from(magic).expect("Must work"),
+                shift: 64 - bits
+            };
+
+            if let Some(table) = try_make_table(game, entry, index, &list) {
+                lookup.push(table);
+                magics.push(entry);
+
+                break;
             }
+
+            // Pieces whose moves rarely collide constructively (like queens) may have no magic for a table of exactly their relevant bits.
+            attempts += 1;
+            if attempts % MAGIC_ATTEMPTS == 0 {
+                bits += 1;
+            }
+        }
+    }
+
+    game.lookup[lookup_index] = lookup;
+    game.magics[lookup_index] = magics;
+}
+
+#[derive(Copy, Clone)]
+pub struct Magic<S : SliderMoves>(pub S);
+
+impl <S : SliderMoves> Magic<S> {
+    pub fn process<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, piece_index: usize) {
+        // We need raycasting to check if magics are valid
+        let slider = Slider(self.0.clone());
+        slider.process(game, piece_index);
+
+        generate_magics(game, piece_index, piece_index, |game, pos, blockers| {
+            Slider::<S>::list_moves(game, piece_index, pos, blockers)
+        });
+
+        // Directional sliders have their own rays for black, so they need magics of their own too
+        let black = game.team_lookup(piece_index, Team::Black);
+        if black != piece_index {
+            generate_magics(game, black, black, |game, pos, blockers| {
+                Slider::<S>::list_moves(game, black, pos, blockers)
+            });
         }
+    }
 
-        game.lookup[piece_index] = lookup;
-        game.magics[piece_index] = magics;
+    /// Every square reachable from `pos` with `blockers`, including squares of the piece's own team.
+    pub fn list_moves<T: BitInt, const N: usize>(game: &Game<T, N>, lookup_index: usize, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        let entry = game.magics[lookup_index][pos];
+        game.lookup[lookup_index][pos][magic_index(entry, blockers)]
     }
 
     pub fn attacks<T: BitInt, const N: usize>(&self, board: &Board<T, N>, piece_index: usize, lookup_index: usize, mask: BitBoard<T>) -> bool {
@@ -154,4 +225,114 @@ impl <S : SliderMoves> Magic<S> {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// As a registered piece, a `Magic` uses its own piece index for its lookups.
+impl<T: BitInt, const N: usize, S : SliderMoves + Send + Sync> PieceRules<T, N> for Magic<S> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        Magic::process(self, game, piece_index);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let lookup_index = board.game.team_lookup(piece_index, board.state.moving_team);
+        Magic::add_actions(self, board, actions, piece_index, lookup_index);
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let lookup_index = board.game.team_lookup(piece_index, board.state.moving_team);
+        Magic::attacks(self, board, piece_index, lookup_index, mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Mover::lines(self, board.game, piece_index, team, square as usize, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+impl<T: BitInt, const N: usize, S : SliderMoves + Send + Sync> Mover<T, N> for Magic<S> {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize) {
+        Magic::process(self, game, lookup_index);
+    }
+
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        Magic::<S>::list_moves(game, game.team_lookup(lookup_index, team), pos, blockers)
+    }
+
+    fn lines(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.moves(game, lookup_index, team.next(), pos, occupancy)
+    }
+}
+
+/// The chess queen with magics. Queen magics would be far too large,
+/// so it looks its moves up in the magics of the bishop and rook registered at `bishop` and `rook` instead.
+#[derive(Copy, Clone)]
+pub struct MagicQueen {
+    pub bishop: usize,
+    pub rook: usize
+}
+
+impl MagicQueen {
+    #[inline(always)]
+    fn list_moves<T: BitInt, const N: usize>(&self, game: &Game<T, N>, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        Magic::<BishopMoves>::list_moves(game, self.bishop, pos, blockers)
+            .or(Magic::<RookMoves>::list_moves(game, self.rook, pos, blockers))
+    }
+}
+
+impl<T: BitInt, const N: usize> PieceRules<T, N> for MagicQueen {
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        let team = board.state.team_to_move();
+        let blockers = board.state.black.or(board.state.white);
+        let piece = piece_index as u8;
+
+        for queen in board.state.pieces[piece_index].and(team).iter() {
+            let moves = self.list_moves(board.game, queen as usize, blockers).and_not(team);
+
+            for to in moves.iter() {
+                actions.push(Action::from(queen as u16, to as u16, piece));
+            }
+        }
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        let team = board.state.team_to_move();
+        let blockers = board.state.black.or(board.state.white);
+
+        board.state.pieces[piece_index]
+            .and(team)
+            .iter()
+            .any(|queen| self.list_moves(board.game, queen as usize, blockers).and(mask).set())
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Mover::lines(self, board.game, piece_index, team, square as usize, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+impl<T: BitInt, const N: usize> Mover<T, N> for MagicQueen {
+    /// The queen has no tables of its own to process.
+    fn process(&mut self, _game: &mut Game<T, N>, _lookup_index: usize) {}
+
+    fn moves(&self, game: &Game<T, N>, _lookup_index: usize, _team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        self.list_moves(game, pos, blockers)
+    }
+
+    fn lines(&self, game: &Game<T, N>, _lookup_index: usize, _team: Team, pos: usize, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.list_moves(game, pos, occupancy)
+    }
+}
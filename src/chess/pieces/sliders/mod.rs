@@ -1,11 +1,26 @@
 
-use crate::{bitboard::{BitBoard, BitInt}, game::{Board, Game}};
+use crate::{bitboard::{BitBoard, BitInt, Edges}, game::{Board, Game}};
 
 pub mod bishop;
 pub mod rook;
 pub mod queen;
 pub mod slider;
 pub mod magics;
+pub mod rider;
+pub mod hopper;
+pub mod lance;
+
+/// The square of `squares` closest to `pos`, where `squares` lie along a single ray from `pos`.
+#[inline(always)]
+pub fn closest<T: BitInt>(pos: usize, squares: BitBoard<T>) -> usize {
+    let square = if BitBoard::index(pos as u16).lt(squares) {
+        squares.bitscan_forward()
+    } else {
+        squares.bitscan_backward()
+    };
+
+    square as usize
+}
 
 #[inline(always)]
 pub fn ray_attacks<T: BitInt, const N: usize>(
@@ -18,12 +33,9 @@ pub fn ray_attacks<T: BitInt, const N: usize>(
 ) -> BitBoard<T> {
     let blocker = ray.and(blockers);
     if blocker.set() {
-        let square = if BitBoard::index(pos as u16).lt(blocker) {
-            blocker.bitscan_forward()
-        } else {
-            blocker.bitscan_backward()
-        };
-        ray.xor(game.lookup[piece_index][dir][square as usize])
+        let square = closest(pos, blocker);
+        // Everything past the blocker is cut off, which also holds for rays of limited range.
+        ray.and_not(game.lookup[piece_index][dir][square])
     } else {
         ray
     }
@@ -41,4 +53,14 @@ pub fn repeat<T: BitInt>(mut pos: BitBoard<T>, apply: impl Fn(BitBoard<T>) -> Bi
         out = out.or(progress);
         pos = progress.and_not(pos);
     }
-}
\ No newline at end of file
+}
+/// Moves `pos` by `x` columns right and `y` rows up, or to nothing if the leap leaves the board.
+pub fn leap<T: BitInt>(pos: BitBoard<T>, edges: &Edges<T>, (x, y): (i16, i16)) -> BitBoard<T> {
+    let pos = if x >= 0 { pos.try_right(edges, x as usize) } else { pos.try_left(edges, -x as usize) };
+    if y >= 0 { pos.try_up(edges, y as usize) } else { pos.try_down(edges, -y as usize) }
+}
+
+/// The ray of a rider, repeating `leap` from `pos` until it leaves the board.
+pub fn ride<T: BitInt>(pos: BitBoard<T>, edges: &Edges<T>, leap_by: (i16, i16)) -> BitBoard<T> {
+    repeat(pos, |pos| leap(pos, edges, leap_by))
+}
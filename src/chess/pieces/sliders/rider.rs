@@ -0,0 +1,68 @@
+use crate::bitboard::{BitBoard, BitInt, Edges};
+
+use super::{ride, slider::SliderMoves};
+
+/// All 8 symmetries of an `(x, y)` leap, without duplicates.
+pub fn symmetries((x, y): (i16, i16)) -> Vec<(i16, i16)> {
+    let mut leaps = vec![];
+    for (x, y) in [ (x, y), (y, x) ] {
+        for (sign_x, sign_y) in [ (1, 1), (1, -1), (-1, 1), (-1, -1) ] {
+            let leap = (x * sign_x, y * sign_y);
+            if !leaps.contains(&leap) {
+                leaps.push(leap);
+            }
+        }
+    }
+
+    leaps
+}
+
+/// A rider repeating a leap in every direction until it's blocked or leaves the board.
+/// `RiderMoves((0, 1))` is a rook, and `RiderMoves((1, 2))` a nightrider.
+#[derive(Copy, Clone)]
+pub struct RiderMoves(pub (i16, i16));
+
+pub const NIGHTRIDER: RiderMoves = RiderMoves((1, 2));
+pub const ZEBRARIDER: RiderMoves = RiderMoves((2, 3));
+pub const CAMELRIDER: RiderMoves = RiderMoves((1, 3));
+
+impl SliderMoves for RiderMoves {
+    fn rays<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>) -> Vec<BitBoard<T>> {
+        symmetries(self.0).into_iter()
+            .map(|leap| ride(pos, edges, leap))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{pieces::{betza::Betza, sliders::{magics::Magic, slider::Slider}, testing::{chess_variant, piece_moves}}, KNIGHT}, game::piece::Piece};
+
+    use super::NIGHTRIDER;
+
+    #[test]
+    fn nightrider_blocking() {
+        let game = chess_variant(vec![ (KNIGHT, Piece::new("knight", 'n', Slider(NIGHTRIDER))) ]);
+        let mut board = game.load("4k3/8/8/2p5/8/4P3/8/N3K3 w - - 0 1");
+
+        // The ray through b3 stops by capturing on c5, and the ray through c2 stops before the pawn on e3.
+        assert_eq!(piece_moves(&mut board, KNIGHT as u8), vec![ "a1b3", "a1c2", "a1c5" ]);
+    }
+
+    #[test]
+    fn nightrider_generators() {
+        let slider = chess_variant(vec![ (KNIGHT, Piece::new("knight", 'n', Slider(NIGHTRIDER))) ]);
+        let magic = chess_variant(vec![ (KNIGHT, Piece::new("knight", 'n', Magic(NIGHTRIDER))) ]);
+        let betza = chess_variant(vec![ (KNIGHT, Piece::new("knight", 'n', Betza::new("NN").expect("Valid notation"))) ]);
+
+        // From d4, the rays through e6 and f5 run to the edge, the pawn on c6 is captured,
+        // and the pawn on f3 blocks its whole ray.
+        for game in [ slider, magic, betza ] {
+            let mut board = game.load("4k3/8/2p5/8/3N4/5P2/8/4K3 w - - 0 1");
+            assert_eq!(
+                piece_moves(&mut board, KNIGHT as u8),
+                vec![ "d4b3", "d4b5", "d4c2", "d4c6", "d4e2", "d4e6", "d4f5", "d4f8", "d4h6" ]
+            );
+        }
+    }
+}
@@ -1,10 +1,16 @@
 
-use crate::{bitboard::{BitBoard, BitInt, Edges}, game::{action::{make_chess_move, Action}, Board, Game}};
+use crate::{bitboard::{BitBoard, BitInt, Edges}, chess::pieces::compound::{mover_attackers_to, Mover}, game::{action::{make_chess_move, Action}, piece::PieceRules, Board, Game, Team}};
 
 use super::{ray_attacks, repeat};
 
-pub trait SliderMoves : Copy + Clone {
+pub trait SliderMoves : Clone {
     fn rays<T: BitInt>(&self, pos: BitBoard<T>, edges: &Edges<T>)  -> Vec<BitBoard<T>>;
+
+    /// Whether the rays depend on the team, like a shogi lance's.
+    /// If so, `rays` gives white's rays, and black's are mirrored from them.
+    fn directional(&self) -> bool {
+        false
+    }
 }
 
 pub struct Slider<S : SliderMoves>(pub S);
@@ -58,26 +64,43 @@ impl<S : SliderMoves> Slider<S> {
 }
 
 impl <S : SliderMoves> Slider<S> {
+    /// Processes white's rays into `piece_index`, and black's into the slot given by `Game::team_lookup`.
     pub fn process<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, piece_index: usize) {
+        self.process_team(game, piece_index, Team::White);
+
+        let black = game.add_team_lookup(piece_index, self.0.directional());
+        if black != piece_index {
+            self.process_team(game, black, Team::Black);
+        }
+    }
+
+    /// Fills `lookup_index` with the rays of `team`, mirroring white's rays for black.
+    fn process_team<T: BitInt, const N: usize>(&self, game: &mut Game<T, N>, lookup_index: usize, team: Team) {
         let edges = game.edges[0];
-        game.lookup[piece_index] = vec![];
+        let bounds = game.bounds;
+        game.lookup[lookup_index] = vec![];
 
-        for index in 0..64 {
+        for index in 0..(bounds.rows * bounds.cols) {
             let slider = BitBoard::index(index);
-            let rays = self.0.rays(slider, &edges);
+            let rays = match team {
+                Team::White => self.0.rays(slider, &edges),
+                Team::Black => self.0.rays(slider.flip_vertical(bounds), &edges).into_iter()
+                    .map(|ray| ray.flip_vertical(bounds))
+                    .collect()
+            };
 
-            while game.lookup[piece_index].len() < rays.len() + 1 {
-                game.lookup[piece_index].push(vec![]);
+            while game.lookup[lookup_index].len() < rays.len() + 1 {
+                game.lookup[lookup_index].push(vec![]);
             }
 
             let mut all = BitBoard::default();
             for (ray_ind, &ray) in rays.iter().enumerate() {
-                game.lookup[piece_index][ray_ind].push(ray);
+                game.lookup[lookup_index][ray_ind].push(ray);
                 all = all.or(ray);
             }
 
             let all_ind = rays.len();
-            game.lookup[piece_index][all_ind].push(all);
+            game.lookup[lookup_index][all_ind].push(all);
         }
     }
 
@@ -87,19 +110,21 @@ impl <S : SliderMoves> Slider<S> {
             return false;
         }
         
+        let lookup_index = board.game.team_lookup(piece_index, board.state.moving_team);
         let blockers = board.state.black.or(board.state.white);
         pieces.iter()
-            .any(|pos| Slider::<S>::can_attack(&board.game, piece_index, pos as usize, blockers, mask))
+            .any(|pos| Slider::<S>::can_attack(&board.game, lookup_index, pos as usize, blockers, mask))
     }
 
     pub fn add_actions<T: BitInt, const N: usize>(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
         let team = board.state.team_to_move();
+        let lookup_index = board.game.team_lookup(piece_index, board.state.moving_team);
         let blockers = board.state.black.or(board.state.white);
         let piece = piece_index as u8;
     
         for pos in board.state.pieces[piece_index].and(team).iter() {
             let from = pos as u16;
-            let moves = Slider::<S>::list_moves(&board.game, piece_index, pos as usize, blockers)
+            let moves = Slider::<S>::list_moves(&board.game, lookup_index, pos as usize, blockers)
                 .and_not(team);
 
             for to in moves.iter() {
@@ -107,4 +132,48 @@ impl <S : SliderMoves> Slider<S> {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+impl<T: BitInt, const N: usize, S : SliderMoves + Send + Sync> PieceRules<T, N> for Slider<S> {
+    fn process(&mut self, game: &mut Game<T, N>, piece_index: usize) {
+        Slider::process(self, game, piece_index);
+    }
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize) {
+        Slider::add_actions(self, board, actions, piece_index);
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool {
+        Slider::attacks(self, board, piece_index, mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        mover_attackers_to(self, board, piece_index, piece_index, square, team, occupancy)
+    }
+
+    fn lines(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        Mover::lines(self, board.game, piece_index, team, square as usize, occupancy)
+    }
+
+    fn reach(&self, game: &Game<T, N>, piece_index: usize, square: u16, team: Team) -> BitBoard<T> {
+        Mover::reach(self, game, piece_index, team, square as usize)
+    }
+}
+
+impl<T: BitInt, const N: usize, S : SliderMoves + Send + Sync> Mover<T, N> for Slider<S> {
+    fn process(&mut self, game: &mut Game<T, N>, lookup_index: usize) {
+        Slider::process(self, game, lookup_index);
+    }
+
+    fn moves(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>) -> BitBoard<T> {
+        Slider::<S>::list_moves(game, game.team_lookup(lookup_index, team), pos, blockers)
+    }
+
+    fn attacks(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, blockers: BitBoard<T>, mask: BitBoard<T>) -> bool {
+        Slider::<S>::can_attack(game, game.team_lookup(lookup_index, team), pos, blockers, mask)
+    }
+
+    fn lines(&self, game: &Game<T, N>, lookup_index: usize, team: Team, pos: usize, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.moves(game, lookup_index, team.next(), pos, occupancy)
+    }
+}
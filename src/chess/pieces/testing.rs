@@ -0,0 +1,53 @@
+use crate::{bitboard::{BitBoard, Bounds}, chess::{Chess, SliderMoves}, game::{piece::Piece, Board, Game, GameTemplate, LookupSlots}};
+
+/// Chess on a board of `bounds`, with each `(index, piece)` of `pieces` replacing the piece at that index.
+/// Only the piece tables follow the new bounds, so positions should only be loaded on 8x8 boards.
+pub fn chess_with(bounds: Bounds, pieces: Vec<(usize, Piece<u64, 6>)>) -> Game<u64, 6> {
+    let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+
+    for (index, piece) in pieces {
+        game.pieces[index] = piece;
+    }
+
+    game.bounds = bounds;
+    game.edges = vec![
+        BitBoard::edges(bounds, 1),
+        BitBoard::edges(bounds, 2)
+    ];
+    game.lookup = LookupSlots::new();
+    game.magics = LookupSlots::new();
+    game.black_lookup = LookupSlots::indexes();
+
+    game.process_pieces();
+
+    game
+}
+
+/// `chess_with` on the usual 8x8 board.
+pub fn chess_variant(pieces: Vec<(usize, Piece<u64, 6>)>) -> Game<u64, 6> {
+    chess_with(Bounds::new(8, 8), pieces)
+}
+
+/// The legal actions of `piece`, sorted in UCI notation.
+pub fn piece_moves(board: &mut Board<u64, 6>, piece: u8) -> Vec<String> {
+    let mut moves: Vec<String> = board.legals().into_iter()
+        .filter(|action| action.piece == piece)
+        .map(|action| board.display_uci_action(action))
+        .collect();
+    moves.sort();
+    moves
+}
+
+/// The index of `square` (like `"d4"`) on a board `cols` files wide.
+pub fn square(cols: u16, square: &str) -> usize {
+    let bytes = square.as_bytes();
+    let file = (bytes[0] - b'a') as u16;
+    let rank = square[1..].parse::<u16>().expect("Valid rank") - 1;
+
+    (rank * cols + file) as usize
+}
+
+/// The `squares` (like `"d4"`) on a board `cols` files wide.
+pub fn squares(cols: u16, squares: &[&str]) -> BitBoard<u64> {
+    squares.iter().fold(BitBoard::default(), |board, &name| board.or(BitBoard::index(square(cols, name) as u16)))
+}
@@ -57,6 +57,17 @@ impl Action {
     pub fn with_info(self, info: u8) -> Action {
         Action { from: self.from, to: self.to, piece: self.piece, info }
     }
+
+    /// Drops `piece` from the moving team's pocket onto `to`.
+    /// Drops have no square to come from, so they're the only actions whose `from` is their `to`.
+    pub fn drop(to: u16, piece: u8) -> Action {
+        Action { from: to, to, piece, info: 0 }
+    }
+
+    #[inline(always)]
+    pub fn is_drop(self) -> bool {
+        self.from == self.to
+    }
 }
 
 #[inline(always)]
@@ -0,0 +1,165 @@
+use crate::bitboard::BitInt;
+
+use super::{action::Action, zobrist::ZobristTable, Board, Game};
+
+/// Options for a differential run. Game `i` is played with the seed `seed + i`, so a failing game can be replayed alone.
+#[derive(Debug, Clone)]
+pub struct DifferentialOptions {
+    pub seed: u64,
+    pub games: usize,
+    pub max_ply: usize,
+    /// Positions to start random games from. When empty, every game starts from the default position.
+    pub positions: Vec<String>
+}
+
+impl Default for DifferentialOptions {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            games: 100,
+            max_ply: 200,
+            positions: vec![]
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DifferentialError {
+    /// Two generators disagree on the legal actions, given in UCI notation.
+    Actions { generator: usize, expected: Vec<String>, found: Vec<String> },
+    /// Saving, loading and saving again didn't produce the same position.
+    RoundTrip { loaded: String },
+    /// The position loaded from its own save doesn't have the same legal actions.
+    RoundTripActions { expected: Vec<String>, found: Vec<String> },
+    /// The hash of the played position differs from the hash of the same position loaded from scratch.
+    Hash { generator: usize, played: u64, loaded: u64 }
+}
+
+#[derive(Debug, Clone)]
+pub struct DifferentialFailure {
+    /// The seed of the failing game.
+    pub seed: u64,
+    pub start: String,
+    pub moves: Vec<String>,
+    pub pos: String,
+    pub error: DifferentialError
+}
+
+fn uci_actions<T : BitInt, const N: usize>(board: &mut Board<T, N>, actions: &[Action]) -> Vec<String> {
+    let mut actions: Vec<String> = actions.iter()
+        .map(|&action| board.display_uci_action(action))
+        .collect();
+    actions.sort();
+    actions
+}
+
+fn check_node<T : BitInt, const N: usize>(
+    boards: &mut [Board<T, N>],
+    tables: &[ZobristTable]
+) -> Result<Vec<Action>, DifferentialError> {
+    let (first, others) = boards.split_first_mut().expect("Differential testing needs at least one game");
+
+    let mut legals = first.legals();
+    legals.sort();
+
+    for (generator, board) in others.iter_mut().enumerate() {
+        let mut other = board.legals();
+        other.sort();
+
+        if other != legals {
+            return Err(DifferentialError::Actions {
+                generator: generator + 1,
+                expected: uci_actions(first, &legals),
+                found: uci_actions(board, &other)
+            });
+        }
+    }
+
+    let pos = boards[0].game.rules.save(&mut boards[0]);
+
+    for (generator, board) in boards.iter_mut().enumerate() {
+        let mut loaded = board.game.load(&pos);
+        let saved = loaded.game.rules.save(&mut loaded);
+
+        if saved != pos {
+            return Err(DifferentialError::RoundTrip { loaded: saved });
+        }
+
+        let mut loaded_legals = loaded.legals();
+        loaded_legals.sort();
+
+        if loaded_legals != legals {
+            return Err(DifferentialError::RoundTripActions {
+                expected: uci_actions(board, &legals),
+                found: uci_actions(&mut loaded, &loaded_legals)
+            });
+        }
+
+        let played = board.game.rules.hash(board, &tables[generator]);
+        let loaded = loaded.game.rules.hash(&mut loaded, &tables[generator]);
+
+        if played != loaded {
+            return Err(DifferentialError::Hash { generator, played, loaded });
+        }
+    }
+
+    Ok(legals)
+}
+
+/// Plays random games on every game in `games` in lockstep, checking at every node that
+/// all of them generate the same legal actions, that positions survive a `save`/`load` round trip,
+/// and that the hash of a played position matches the hash of the same position loaded from scratch.
+///
+/// The games should implement the same rules, differing only in how they generate actions.
+/// Returns the number of nodes checked.
+pub fn run_differential<T : BitInt, const N: usize>(
+    games: &[&Game<T, N>],
+    options: &DifferentialOptions
+) -> Result<usize, Box<DifferentialFailure>> {
+    assert!(!games.is_empty(), "Differential testing needs at least one game");
+
+    let mut nodes = 0;
+
+    for game_ind in 0..options.games {
+        let seed = options.seed + game_ind as u64;
+        let mut rng = fastrand::Rng::with_seed(seed);
+
+        let start = if options.positions.is_empty() {
+            games[0].default_pos.clone()
+        } else {
+            options.positions[game_ind % options.positions.len()].clone()
+        };
+
+        let mut boards: Vec<Board<T, N>> = games.iter().map(|game| game.load(&start)).collect();
+        let tables: Vec<ZobristTable> = boards.iter_mut()
+            .map(|board| board.game.rules.gen_zobrist(board, seed))
+            .collect();
+
+        let mut moves = vec![];
+
+        for _ in 0..=options.max_ply {
+            let legals = check_node(&mut boards, &tables).map_err(|error| Box::new(DifferentialFailure {
+                seed,
+                start: start.clone(),
+                moves: moves.clone(),
+                pos: boards[0].game.rules.save(&mut boards[0]),
+                error
+            }))?;
+
+            nodes += 1;
+
+            if legals.is_empty() || moves.len() == options.max_ply {
+                break;
+            }
+
+            let action = legals[rng.usize(..legals.len())];
+            moves.push(boards[0].display_uci_action(action));
+
+            for board in boards.iter_mut() {
+                board.play(action);
+            }
+        }
+    }
+
+    Ok(nodes)
+}
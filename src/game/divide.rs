@@ -0,0 +1,199 @@
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::bitboard::BitInt;
+
+use super::Board;
+
+/// Parses divide output of a reference engine.
+///
+/// Lines are expected in the common `e2e4: 20` format (`e2e4 - 20`, as printed by `perft_debug`, also works).
+/// Lines that don't fit either format, like totals or headers, are ignored.
+pub fn parse_divide(output: &str) -> HashMap<String, usize> {
+    let mut divide = HashMap::default();
+
+    for line in output.lines() {
+        let Some((action, nodes)) = line.split_once(':').or_else(|| line.split_once(" - ")) else {
+            continue;
+        };
+
+        let action = action.trim();
+        if action.is_empty() || action.contains(' ') {
+            continue;
+        }
+
+        if let Ok(nodes) = nodes.trim().parse::<usize>() {
+            divide.insert(action.to_string(), nodes);
+        }
+    }
+
+    divide
+}
+
+/// Where our divide first disagrees with a reference.
+#[derive(Debug, Clone)]
+pub struct DivideMismatch {
+    /// Actions played from the root position to reach the mismatching position.
+    pub moves: Vec<String>,
+    /// The mismatching position, as saved by the game's rules.
+    pub pos: String,
+    pub depth: usize,
+    /// Actions the reference found, but we didn't.
+    pub missing: Vec<(String, usize)>,
+    /// Actions we found, but the reference didn't.
+    pub extra: Vec<(String, usize)>,
+    /// Actions both sides found, with the reference's and our node counts respectively.
+    pub differing: Vec<(String, usize, usize)>
+}
+
+impl DivideMismatch {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.differing.is_empty()
+    }
+}
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    /// Compares our divide at `depth` against a reference divide, without descending.
+    pub fn compare_divide(&mut self, depth: usize, reference: &str) -> DivideMismatch {
+        let reference = parse_divide(reference);
+        let ours = self.divide_uci(depth);
+
+        let mut missing: Vec<(String, usize)> = reference.iter()
+            .filter(|(action, _)| !ours.iter().any(|(ours, _)| ours == *action))
+            .map(|(action, &nodes)| (action.clone(), nodes))
+            .collect();
+        missing.sort();
+
+        let mut extra = vec![];
+        let mut differing = vec![];
+
+        for (action, nodes) in ours {
+            match reference.get(&action) {
+                None => extra.push((action, nodes)),
+                Some(&expected) if expected != nodes => differing.push((action, expected, nodes)),
+                _ => {}
+            }
+        }
+
+        DivideMismatch {
+            moves: vec![],
+            pos: self.game.rules.save(self),
+            depth,
+            missing,
+            extra,
+            differing
+        }
+    }
+
+    /// Finds the minimal position where our divide disagrees with a reference engine.
+    ///
+    /// `reference` is given the root position, the actions played from it and a depth,
+    /// and should return the reference engine's divide output for that position.
+    /// If every action matches, or only node counts differ, the first differing action is played
+    /// and the search continues one ply deeper, until missing or extra actions are found
+    /// or the counts differ at depth 1.
+    pub fn find_divide_mismatch(
+        &mut self,
+        depth: usize,
+        mut reference: impl FnMut(&str, &[String], usize) -> String
+    ) -> Option<DivideMismatch> {
+        let root = self.game.rules.save(self);
+        let mut board = self.clone();
+        let mut moves: Vec<String> = vec![];
+        let mut depth = depth;
+        let mut parent: Option<DivideMismatch> = None;
+
+        loop {
+            let output = reference(&root, &moves, depth);
+            let mut mismatch = board.compare_divide(depth, &output);
+            mismatch.moves = moves.clone();
+
+            // The reference disagrees with itself between plies, so the parent is as deep as we can go.
+            if mismatch.is_empty() {
+                return parent;
+            }
+
+            if !mismatch.missing.is_empty() || !mismatch.extra.is_empty() || depth <= 1 {
+                return Some(mismatch);
+            }
+
+            let action = mismatch.differing[0].0.clone();
+            board.play_action(&action);
+            moves.push(action);
+            depth -= 1;
+            parent = Some(mismatch);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{Chess, MagicMoves}, game::GameTemplate};
+
+    use super::parse_divide;
+
+    #[test]
+    fn divide_parsing() {
+        let divide = parse_divide("e2e4: 20\nd2d4: 20\n\nNodes searched: 40\ng1f3 - 20");
+
+        assert_eq!(divide.len(), 3);
+        assert_eq!(divide.get("e2e4"), Some(&20));
+        assert_eq!(divide.get("g1f3"), Some(&20));
+    }
+
+    #[test]
+    fn divide_mismatch_descends() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.load("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+
+        // A reference which believes black has an extra king move after h1h2.
+        let mismatch = board.find_divide_mismatch(3, |root, moves, depth| {
+            let mut reference = chess.load(root);
+            for action in moves {
+                reference.play_action(action);
+            }
+
+            let mut lines: Vec<String> = reference.divide_uci(depth).into_iter()
+                .map(|(action, nodes)| {
+                    let nodes = match (moves.len(), action.as_str()) {
+                        (0, "h1h2") => nodes + 3,
+                        (1, _) if moves[0] == "h1h2" => nodes + 1,
+                        _ => nodes
+                    };
+                    format!("{}: {}", action, nodes)
+                })
+                .collect();
+
+            if moves.len() == 2 && moves[0] == "h1h2" {
+                lines.push("e8e9: 1".to_string());
+            }
+
+            lines.join("\n")
+        }).expect("Mismatch should be found");
+
+        assert_eq!(mismatch.moves.len(), 2);
+        assert_eq!(mismatch.moves[0], "h1h2");
+        assert_eq!(mismatch.depth, 1);
+        assert_eq!(mismatch.missing, vec![("e8e9".to_string(), 1)]);
+        assert!(mismatch.extra.is_empty());
+    }
+
+    #[test]
+    fn divide_matches_itself() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.default();
+
+        let mismatch = board.find_divide_mismatch(2, |root, moves, depth| {
+            let mut reference = chess.load(root);
+            for action in moves {
+                reference.play_action(action);
+            }
+
+            reference.divide_uci(depth).into_iter()
+                .map(|(action, nodes)| format!("{}: {}", action, nodes))
+                .collect::<Vec<_>>()
+                .join("\n")
+        });
+
+        assert!(mismatch.is_none());
+    }
+}
@@ -0,0 +1,311 @@
+use crate::bitboard::{BitBoard, BitInt, Bounds};
+
+use super::{action::Action, promotion::is_promotion, Board, BoardState, Team};
+
+/// How a single piece type is dropped from the pocket.
+#[derive(Clone, Debug)]
+pub struct Drop<T : BitInt> {
+    /// Indexed by team; the squares it can be dropped on, when they're empty.
+    pub squares: [BitBoard<T>; 2],
+    /// Indexed by team; the squares where a dropped piece counts as unmoved (so a pawn dropped on its starting rank can move twice).
+    pub unmoved: [BitBoard<T>; 2],
+    /// Whether it can't be dropped onto a file already holding an unpromoted piece of its type and team (like nifu in shogi).
+    pub one_per_file: bool
+}
+
+/// A game's drop rules.
+///
+/// In a game with drops, captured pieces of a droppable type enter the capturer's pocket (demoted first, if they were promoted),
+/// and `Board::piece_actions` lists drops out of the moving team's pocket with `Action::drop`.
+#[derive(Clone, Debug)]
+pub struct DropRules<T : BitInt> {
+    bounds: Bounds,
+    files: Vec<BitBoard<T>>,
+    /// Indexed by piece type; how each piece type is dropped, if it can be.
+    pub pieces: Vec<Option<Drop<T>>>
+}
+
+impl<T : BitInt> DropRules<T> {
+    /// No drops, for no piece types yet.
+    pub fn new(bounds: Bounds) -> DropRules<T> {
+        let files = (0..bounds.cols)
+            .map(|file| BitBoard::edges_left(bounds, file + 1).and_not(BitBoard::edges_left(bounds, file)))
+            .collect();
+
+        DropRules { bounds, files, pieces: vec![] }
+    }
+
+    /// Lets `piece` be dropped on `squares` (indexed by team).
+    pub fn drop_on(mut self, piece: usize, squares: [BitBoard<T>; 2]) -> DropRules<T> {
+        if self.pieces.len() <= piece {
+            self.pieces.resize(piece + 1, None);
+        }
+
+        let unmoved = [ BitBoard::default(), BitBoard::default() ];
+        self.pieces[piece] = Some(Drop { squares, unmoved, one_per_file: false });
+        self
+    }
+
+    /// Lets `piece` be dropped anywhere but the last `forbidden_ranks` ranks ahead of each team, where it could never move again.
+    pub fn drop(self, piece: usize, forbidden_ranks: u16) -> DropRules<T> {
+        let board = BitBoard::edges_left(self.bounds, self.bounds.cols);
+        let squares = [
+            board.and_not(BitBoard::edges_up(self.bounds, forbidden_ranks)),
+            board.and_not(BitBoard::edges_down(self.bounds, forbidden_ranks))
+        ];
+
+        self.drop_on(piece, squares)
+    }
+
+    /// Counts `piece` as unmoved when it's dropped onto the first `ranks` ranks of its team.
+    pub fn unmoved(mut self, piece: usize, ranks: u16) -> DropRules<T> {
+        let unmoved = [ BitBoard::edges_down(self.bounds, ranks), BitBoard::edges_up(self.bounds, ranks) ];
+        if let Some(drop) = self.pieces.get_mut(piece).and_then(|drop| drop.as_mut()) {
+            drop.unmoved = unmoved;
+        }
+        self
+    }
+
+    /// Forbids dropping `piece` onto a file which already has an unpromoted one of its team.
+    pub fn one_per_file(mut self, piece: usize) -> DropRules<T> {
+        if let Some(drop) = self.pieces.get_mut(piece).and_then(|drop| drop.as_mut()) {
+            drop.one_per_file = true;
+        }
+        self
+    }
+
+    /// Whether the game has drops at all.
+    #[inline(always)]
+    pub fn enabled(&self) -> bool {
+        !self.pieces.is_empty()
+    }
+
+    #[inline(always)]
+    pub fn drop_rule(&self, piece: usize) -> Option<&Drop<T>> {
+        self.pieces.get(piece).and_then(|drop| drop.as_ref())
+    }
+
+    /// Adds every drop the moving team can make out of its pocket.
+    pub fn add_drops<const N: usize>(&self, state: &BoardState<T, N>, actions: &mut Vec<Action>) {
+        let team = state.moving_team;
+        let occupied = state.white.or(state.black);
+
+        for (piece, drop) in self.pieces.iter().enumerate() {
+            let drop = match drop {
+                Some(drop) if state.pockets[team.index()][piece] > 0 => drop,
+                _ => continue
+            };
+
+            let mut squares = drop.squares[team.index()].and_not(occupied);
+
+            if drop.one_per_file {
+                let unpromoted = state.pieces[piece].and(state.team(team)).and_not(state.promoted);
+                for &file in &self.files {
+                    if file.and(unpromoted).set() {
+                        squares = squares.and_not(file);
+                    }
+                }
+            }
+
+            for square in squares.iter() {
+                actions.push(Action::drop(square as u16, piece as u8));
+            }
+        }
+    }
+
+    /// Takes the dropped piece out of the moving team's pocket and places it on `action.to`.
+    pub fn make_drop_move<const N: usize>(&self, state: &mut BoardState<T, N>, action: Action) {
+        let piece = action.piece as usize;
+        let team = state.moving_team;
+        let to = BitBoard::index(action.to);
+
+        state.pockets[team.index()][piece] -= 1;
+        state.pieces[piece] = state.pieces[piece].or(to);
+
+        match team {
+            Team::White => state.white = state.white.or(to),
+            Team::Black => state.black = state.black.or(to)
+        }
+
+        let unmoved = self.drop_rule(piece).is_some_and(|drop| drop.unmoved[team.index()].and(to).set());
+        if unmoved {
+            state.first_move = state.first_move.or(to);
+        }
+    }
+}
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    /// After `action` was played, tracks where promoted pieces went,
+    /// and puts the piece it captured (as `(square, piece)`, found before the action) into the moving team's pocket.
+    pub fn pocket_capture(&mut self, action: Action, captured: Option<(u16, usize)>) {
+        let team = self.state.moving_team;
+        let from = BitBoard::index(action.from);
+        let to = BitBoard::index(action.to);
+
+        let was_promoted = self.state.promoted.and(from).set();
+        let mut promoted = self.state.promoted.and_not(from);
+
+        if let Some((square, mut piece)) = captured {
+            let square = BitBoard::index(square);
+            if promoted.and(square).set() {
+                piece = self.game.promotion.demotion(piece);
+            }
+            promoted = promoted.and_not(square);
+
+            if self.game.drops.drop_rule(piece).is_some() {
+                self.state.pockets[team.index()][piece] += 1;
+            }
+        }
+
+        if is_promotion(self, action) || was_promoted {
+            promoted = promoted.or(to);
+        }
+        self.state.promoted = promoted;
+    }
+
+    /// Loads pockets from their FEN notation (like `QPPn`), with white's pieces in uppercase.
+    pub fn load_pockets(&mut self, pockets: &str) {
+        let piece_map = self.game.piece_map();
+        for char in pockets.chars() {
+            if let Some(piece) = piece_map.iter().position(|&n| n == char.to_ascii_lowercase()) {
+                let team = if char.is_lowercase() { Team::Black } else { Team::White };
+                self.state.pockets[team.index()][piece] += 1;
+            }
+        }
+    }
+
+    /// Saves the pockets in their FEN notation, white's pieces first.
+    pub fn save_pockets(&self) -> String {
+        let piece_map = self.game.piece_map();
+        let mut pockets = String::new();
+
+        for team in [ Team::White, Team::Black ] {
+            for (piece, &count) in self.state.pockets[team.index()].iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+
+                let char = match team {
+                    Team::White => piece_map[piece].to_ascii_uppercase(),
+                    Team::Black => piece_map[piece]
+                };
+
+                for _ in 0..count {
+                    pockets.push(char);
+                }
+            }
+        }
+
+        pockets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::{BitBoard, Bounds}, chess::{Chess, SliderMoves, KNIGHT, PAWN, QUEEN}, game::{drops::DropRules, Game, GameTemplate, Team}};
+
+    fn drop_chess() -> Game<u64, 6> {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+        let bounds = Bounds::new(8, 8);
+
+        // Like Crazyhouse, pawns can't be dropped on either back rank.
+        let ranks = BitBoard::edges_left(bounds, 8)
+            .and_not(BitBoard::edges_up(bounds, 1))
+            .and_not(BitBoard::edges_down(bounds, 1));
+
+        game.drops = (0..5).fold(DropRules::new(bounds), |drops, piece| drops.drop(piece, 0))
+            .drop_on(PAWN, [ ranks, ranks ])
+            .unmoved(PAWN, 2);
+        game
+    }
+
+    #[test]
+    fn pockets_round_trip() {
+        let game = drop_chess();
+
+        let fen = "4k3/8/8/3Q~4/8/8/8/4K3[PPNq] w - - 0 1";
+        let mut board = game.load(fen);
+        assert_eq!(board.state.pockets[Team::White.index()], [ 2, 1, 0, 0, 0, 0 ]);
+        assert_eq!(board.state.pockets[Team::Black.index()], [ 0, 0, 0, 0, 1, 0 ]);
+        assert_eq!(game.rules.save(&mut board), fen);
+    }
+
+    #[test]
+    fn drops() {
+        let game = drop_chess();
+
+        // Pawns can't be dropped on either back rank, but knights can go anywhere empty.
+        let mut board = game.load("4k3/8/8/8/8/8/8/4K3[PN] w - - 0 1");
+        let actions = board.legals();
+        assert_eq!(actions.iter().filter(|action| action.is_drop() && action.piece == PAWN as u8).count(), 48);
+        assert_eq!(actions.iter().filter(|action| action.is_drop() && action.piece == KNIGHT as u8).count(), 64 - 2);
+
+        // A pawn dropped on its starting rank can still move twice.
+        board.play_action("P@e2");
+        board.play_null();
+        board.find_action("e2e4");
+
+        // The dropped piece leaves the pocket, and comes back once the drop is undone.
+        let mut board = game.load("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1");
+        let state = board.play_action("N@c3");
+        assert_eq!(board.state.pockets[Team::White.index()][KNIGHT], 0);
+        assert_eq!(game.rules.save(&mut board), "4k3/8/8/8/8/2N5/8/4K3[] b - - 1 1");
+        board.restore(state);
+        assert_eq!(board.state.pockets[Team::White.index()][KNIGHT], 1);
+    }
+
+    #[test]
+    fn captures_enter_pockets() {
+        let game = drop_chess();
+
+        // A captured queen that was promoted goes back into the pocket as a pawn.
+        let mut board = game.load("4k3/8/8/3q~4/4P3/8/8/4K3[] w - - 0 1");
+        board.play_action("e4d5");
+        assert_eq!(board.state.pockets[Team::White.index()][PAWN], 1);
+        assert_eq!(board.state.pockets[Team::White.index()][QUEEN], 0);
+        assert!(board.state.promoted.empty());
+
+        let mut board = game.load("4k3/8/8/3q4/4P3/8/8/4K3[] w - - 0 1");
+        board.play_action("e4d5");
+        assert_eq!(board.state.pockets[Team::White.index()][QUEEN], 1);
+
+        // Promoted pieces stay marked as they move.
+        let mut board = game.load("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1");
+        board.play_action("a7a8q");
+        board.play_null();
+        board.play_action("a8a1");
+        assert_eq!(game.rules.save(&mut board), "4k3/8/8/8/8/8/8/Q~3K3[] b - - 1 2");
+    }
+
+    #[test]
+    fn one_per_file() {
+        let mut game = drop_chess();
+        game.drops = DropRules::new(Bounds::new(8, 8)).drop(PAWN, 1).one_per_file(PAWN);
+
+        // Like nifu, the e-file already has a white pawn, while the d-file only has a black one.
+        let mut board = game.load("4k3/3p4/8/8/8/8/4P3/4K3[P] w - - 0 1");
+        let files: Vec<u16> = board.legals().into_iter()
+            .filter(|action| action.is_drop())
+            .map(|action| action.to % 8)
+            .collect();
+        assert!(!files.contains(&4) && files.contains(&3));
+    }
+
+    #[test]
+    fn pockets_are_hashed() {
+        let game = drop_chess();
+        let mut board = game.load("4k3/8/8/8/8/8/8/4K3[] w - - 0 1");
+        let table = game.rules.gen_zobrist(&mut board, 64);
+
+        let hashes: Vec<u64> = [ "[]", "[P]", "[PP]", "[p]", "[N]" ].iter()
+            .map(|pockets| {
+                let mut board = game.load(&format!("4k3/8/8/8/8/8/8/4K3{} w - - 0 1", pockets));
+                game.rules.hash(&mut board, &table)
+            })
+            .collect();
+
+        for (index, hash) in hashes.iter().enumerate() {
+            assert!(!hashes[index + 1..].contains(hash));
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::bitboard::{BitBoard, BitInt};
+
+use super::{Board, BoardState, Game, Team};
+
+/// The pieces each team has, as piece indexes. Parsed from signatures like `KRPvKR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaterialSignature {
+    pub white: Vec<usize>,
+    pub black: Vec<usize>
+}
+
+impl MaterialSignature {
+    /// Parses a signature where white's and black's pieces are separated by a `v`,
+    /// and pieces are named by their characters in `piece_map` (case insensitive).
+    pub fn parse(signature: &str, piece_map: &[char]) -> Option<MaterialSignature> {
+        let (white, black) = signature.split_once('v')?;
+
+        let parse_side = |side: &str| -> Option<Vec<usize>> {
+            side.chars()
+                .map(|char| piece_map.iter().position(|&piece| piece == char.to_ascii_lowercase()))
+                .collect()
+        };
+
+        Some(MaterialSignature {
+            white: parse_side(white)?,
+            black: parse_side(black)?
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionOptions<T : BitInt> {
+    pub seed: u64,
+    pub moving_team: Team,
+    /// Squares each listed piece type may never be placed on, besides those the game's own rules already rule out
+    /// (see `Game::unplaceable`).
+    pub forbidden: Vec<(usize, BitBoard<T>)>,
+    /// How many placements to try before giving up on finding a legal one.
+    pub max_attempts: usize
+}
+
+impl<T : BitInt> Default for PositionOptions<T> {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            moving_team: Team::White,
+            forbidden: vec![],
+            max_attempts: 1000
+        }
+    }
+}
+
+impl<T : BitInt> PositionOptions<T> {
+    pub fn forbid(mut self, piece: usize, squares: BitBoard<T>) -> Self {
+        self.forbidden.push((piece, squares));
+        self
+    }
+}
+
+impl<T : BitInt, const N: usize> Game<T, N> {
+    /// The squares `piece` of `team` can never stand on by the game's rules, like pawns on the back ranks in chess.
+    pub fn unplaceable(&self, piece: usize, team: Team) -> BitBoard<T> {
+        self.rules.unplaceable(self, piece, team)
+    }
+
+    fn place_randomly(
+        &self,
+        rng: &mut fastrand::Rng,
+        signature: &MaterialSignature,
+        options: &PositionOptions<T>
+    ) -> Option<BoardState<T, N>> {
+        let squares = self.bounds.rows * self.bounds.cols;
+        let mut state = BoardState::new();
+        state.moving_team = options.moving_team;
+
+        for (team, pieces) in [(Team::White, &signature.white), (Team::Black, &signature.black)] {
+            for &piece in pieces {
+                let forbidden = options.forbidden.iter()
+                    .filter(|(forbidden, _)| *forbidden == piece)
+                    .fold(state.white.or(state.black).or(self.unplaceable(piece, team)), |acc, (_, squares)| acc.or(*squares));
+
+                let available: Vec<u16> = (0..squares)
+                    .filter(|&square| BitBoard::index(square).and(forbidden).empty())
+                    .collect();
+
+                if available.is_empty() {
+                    return None;
+                }
+
+                let square = BitBoard::index(available[rng.usize(..available.len())]);
+                state.pieces[piece] = state.pieces[piece].or(square);
+
+                match team {
+                    Team::White => state.white = state.white.or(square),
+                    Team::Black => state.black = state.black.or(square)
+                }
+            }
+        }
+
+        Some(state)
+    }
+
+    /// Generates a random legal position with the given material.
+    ///
+    /// A position is legal if `GameRules::is_legal` accepts it, meaning the side not to move can't be captured.
+    /// The placement is saved and loaded back through the game's rules, so state like first moves
+    /// is whatever the game would infer from its own notation.
+    pub fn random_position(&self, signature: &MaterialSignature, options: &PositionOptions<T>) -> Option<Board<'_, T, N>> {
+        let mut rng = fastrand::Rng::with_seed(options.seed);
+
+        for _ in 0..options.max_attempts {
+            let Some(state) = self.place_randomly(&mut rng, signature, options) else {
+                continue;
+            };
+
+            let mut board = self.init();
+            board.state = state;
+
+            if !self.rules.is_legal(&mut board) {
+                continue;
+            }
+
+            let pos = self.rules.save(&mut board);
+            return Some(self.load(&pos));
+        }
+
+        None
+    }
+
+    /// Generates `count` random legal positions with the given material, in the game's notation.
+    /// Position `i` is generated with the seed `seed + i`.
+    pub fn random_positions(&self, signature: &MaterialSignature, options: &PositionOptions<T>, count: usize) -> Vec<String> {
+        (0..count)
+            .filter_map(|index| {
+                let options = PositionOptions { seed: options.seed + index as u64, ..options.clone() };
+                let mut board = self.random_position(signature, &options)?;
+                Some(self.rules.save(&mut board))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{crazyhouse::Crazyhouse, Chess, MagicMoves, KING, PAWN, ROOK}, game::{GameTemplate, Team}, shogi::{self, Shogi}};
+
+    use super::{MaterialSignature, PositionOptions};
+
+    #[test]
+    fn signature_parsing() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let signature = MaterialSignature::parse("KRPvKR", &chess.piece_map()).expect("Valid signature");
+
+        assert_eq!(signature.white, vec![KING, ROOK, PAWN]);
+        assert_eq!(signature.black, vec![KING, ROOK]);
+        assert!(MaterialSignature::parse("KXvK", &chess.piece_map()).is_none());
+    }
+
+    #[test]
+    fn random_positions_are_legal() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let signature = MaterialSignature::parse("KRPvKR", &chess.piece_map()).expect("Valid signature");
+
+        let edges = chess.edges[0];
+        let options = PositionOptions { moving_team: Team::Black, ..Default::default() }
+            .forbid(PAWN, edges.top.or(edges.bottom));
+
+        let positions = chess.random_positions(&signature, &options, 50);
+        assert_eq!(positions.len(), 50);
+
+        for pos in positions {
+            let mut board = chess.load(&pos);
+
+            assert_eq!(board.state.moving_team, Team::Black);
+            assert_eq!(board.state.white.count(), 3);
+            assert_eq!(board.state.black.count(), 2);
+            assert!(board.state.pieces[PAWN].and(edges.top.or(edges.bottom)).empty());
+            assert!(chess.rules.is_legal(&mut board));
+        }
+    }
+
+    #[test]
+    fn default_positions_follow_game_rules() {
+        // Chess pawns start past their own back rank and have to promote on the other, so they're never placed on either.
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let signature = MaterialSignature::parse("KPPPvKPPP", &chess.piece_map()).expect("Valid signature");
+        let edges = chess.edges[0];
+        let back_ranks = edges.top.or(edges.bottom);
+
+        for pos in chess.random_positions(&signature, &PositionOptions::default(), 50) {
+            let board = chess.load(&pos);
+            let pawns = board.state.pieces[PAWN];
+
+            assert!(pawns.and(board.state.white).and(back_ranks).empty(), "{}", pos);
+            assert!(pawns.and(board.state.black).and(back_ranks).empty(), "{}", pos);
+        }
+
+        // Crazyhouse plays by the chess rules, so its pawns stay off the back ranks too.
+        let crazyhouse = Crazyhouse::<MagicMoves>::create::<u64, 6>();
+        for pos in crazyhouse.random_positions(&signature, &PositionOptions::default(), 50) {
+            let board = crazyhouse.load(&pos);
+            assert!(board.state.pieces[PAWN].and(back_ranks).empty(), "{}", pos);
+        }
+
+        // Shogi knights can never move again from the last two ranks.
+        let shogi = Shogi::create::<u128, 14>();
+        assert_eq!(shogi.unplaceable(shogi::KNIGHT, Team::White).count(), 18);
+        let signature = MaterialSignature::parse("KNNNvKNNN", &shogi.piece_map()).expect("Valid signature");
+
+        for pos in shogi.random_positions(&signature, &PositionOptions::default(), 50) {
+            let board = shogi.load(&pos);
+            let knights = board.state.pieces[shogi::KNIGHT];
+
+            assert!(knights.and(board.state.white).and(shogi.unplaceable(shogi::KNIGHT, Team::White)).empty());
+            assert!(knights.and(board.state.black).and(shogi.unplaceable(shogi::KNIGHT, Team::Black)).empty());
+        }
+    }
+}
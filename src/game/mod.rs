@@ -1,15 +1,29 @@
-use std::ops::Index;
+use std::ops::{Index, IndexMut};
 
 use arrayvec::ArrayVec;
 use rustc_hash::FxHashMap as HashMap;
 
 use action::{ActionRecord, Action};
+use drops::DropRules;
+use piece::Piece;
+use promotion::PromotionRules;
+use royalty::Royalty;
 use zobrist::ZobristTable;
 
 use crate::bitboard::{BitBoard, BitInt, Bounds, Edges};
 
 pub mod action;
+pub mod differential;
+pub mod divide;
+pub mod drops;
+pub mod generate;
 pub mod perft;
+pub mod pins;
+pub mod piece;
+pub mod playout;
+pub mod promotion;
+pub mod royalty;
+pub mod see;
 pub mod suite;
 pub mod zobrist;
 
@@ -22,8 +36,68 @@ pub type AttackDirections<T> = Vec<BitBoard<T>>;
 
 pub type AttackLookup<T> = Vec<AttackDirections<T>>;
 
+/// Lookup slots, indexed by the piece type.
+/// Slots past the piece types are reserved with `Game::add_lookup`, for pieces that need more than one lookup.
+///
+/// The piece types' own slots are kept inline, so finding them doesn't go through another allocation.
+#[derive(Clone)]
+pub struct LookupSlots<L, const N: usize> {
+    pieces: [ L; N ],
+    extra: Vec<L>
+}
+
+impl<L : Default, const N: usize> LookupSlots<L, N> {
+    /// Empty slots for each piece type, and none past them.
+    pub fn new() -> LookupSlots<L, N> {
+        LookupSlots { pieces: std::array::from_fn(|_| L::default()), extra: vec![] }
+    }
+}
+
+impl<L : Default, const N: usize> Default for LookupSlots<L, N> {
+    fn default() -> LookupSlots<L, N> {
+        LookupSlots::new()
+    }
+}
+
+impl<const N: usize> LookupSlots<usize, N> {
+    /// Slots holding their own index, for each piece type.
+    pub fn indexes() -> LookupSlots<usize, N> {
+        LookupSlots { pieces: std::array::from_fn(|index| index), extra: vec![] }
+    }
+}
+
+impl<L, const N: usize> LookupSlots<L, N> {
+    pub fn len(&self) -> usize {
+        N + self.extra.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn push(&mut self, slot: L) {
+        self.extra.push(slot);
+    }
+}
+
+impl<L, const N: usize> Index<usize> for LookupSlots<L, N> {
+    type Output = L;
+
+    #[inline(always)]
+    fn index(&self, index: usize) -> &L {
+        if index < N { &self.pieces[index] } else { &self.extra[index - N] }
+    }
+}
+
+impl<L, const N: usize> IndexMut<usize> for LookupSlots<L, N> {
+    #[inline(always)]
+    fn index_mut(&mut self, index: usize) -> &mut L {
+        if index < N { &mut self.pieces[index] } else { &mut self.extra[index - N] }
+    }
+}
+
 /// Indexed by the piece type; find a piece's attack lookups.
-pub type PieceLookup<T, const N: usize> = [ AttackLookup<T>; N ];
+pub type PieceLookup<T, const N: usize> = LookupSlots<AttackLookup<T>, N>;
 
 #[derive(Clone, Copy)]
 pub struct MagicEntry<T : BitInt> {
@@ -35,15 +109,38 @@ pub struct MagicEntry<T : BitInt> {
 /// Indexed by the piece type; find a piece's magics.
 pub type PieceMagics<T> = Vec<MagicEntry<T>>;
 
-pub type MagicLookUp<T, const N: usize> = [ PieceMagics<T>; N ];
+pub type MagicLookUp<T, const N: usize> = LookupSlots<PieceMagics<T>, N>;
+
+/// The piece types that castle with each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Castling {
+    pub king: usize,
+    pub rook: usize
+}
 
 pub struct Game<T : BitInt, const N: usize> {
-    pub rules: Box<dyn GameRules<T, N>>,
+    pub rules: Box<dyn GameRules<T, N> + Send + Sync>,
+    /// Indexed by the piece type; the registered pieces of the game.
+    pub pieces: Vec<Piece<T, N>>,
     pub edges: Vec<Edges<T>>,
     pub bounds: Bounds,
     pub default_pos: String,
+    pub promotion: PromotionRules<T>,
+    pub drops: DropRules<T>,
+    pub royalty: Royalty,
+    /// The pieces named "king" and "rook", found once by `Game::process_pieces` so castling doesn't search for them.
+    pub castling: Option<Castling>,
+    /// Indexed by the piece type; how much each piece type is worth, like for `Board::see`.
+    /// Piece types past the end are worth nothing.
+    pub values: Vec<i32>,
     pub lookup: PieceLookup<T, N>,
-    pub magics: MagicLookUp<T, N>
+    pub magics: MagicLookUp<T, N>,
+    /// Indexed like `lookup`; the slot holding black's tables for each slot.
+    /// This is the slot itself, unless a directional piece mirrored its tables into a slot of their own.
+    pub black_lookup: LookupSlots<usize, N>,
+    /// Indexed by the piece type, then the square; the squares a piece of that type on either team could attack the square from.
+    /// Filled in by `Game::process_pieces`, so piece types with no pieces there can be skipped when looking for attackers.
+    pub reach: Vec<Vec<BitBoard<T>>>
 }
 
 impl<T : BitInt, const N: usize> Game<T, N> {
@@ -60,6 +157,31 @@ impl<T : BitInt, const N: usize> Game<T, N> {
         board.load(pos);
         board
     }
+
+    /// Reserves an empty lookup (and magics) slot past the piece types, returning its index.
+    pub fn add_lookup(&mut self) -> usize {
+        self.lookup.push(vec![]);
+        self.magics.push(vec![]);
+        self.black_lookup.push(self.lookup.len() - 1);
+        self.lookup.len() - 1
+    }
+
+    /// Sets up black's slot for `lookup_index`, reserving a new one if `directional` (so black's tables can be mirrored into it),
+    /// or sharing `lookup_index` otherwise. Returns black's slot.
+    pub fn add_team_lookup(&mut self, lookup_index: usize, directional: bool) -> usize {
+        let black = if directional { self.add_lookup() } else { lookup_index };
+        self.black_lookup[lookup_index] = black;
+        black
+    }
+
+    /// The slot holding `team`'s tables for `lookup_index`.
+    #[inline(always)]
+    pub fn team_lookup(&self, lookup_index: usize, team: Team) -> usize {
+        match team {
+            Team::White => lookup_index,
+            Team::Black => self.black_lookup[lookup_index]
+        }
+    }
 }
 
 pub trait GameTemplate {
@@ -78,16 +200,17 @@ pub trait GameRules<T : BitInt, const N: usize> {
     fn load(&self, board: &mut Board<T, N>, pos: &str);
     fn save(&self, board: &mut Board<T, N>) -> String;
 
-    fn piece_map(&self) -> Vec<char>;
-
     fn actions(&self, board: &mut Board<T, N>) -> Vec<Action>;
     fn attacks(&self, board: &mut Board<T, N>, mask: BitBoard<T>) -> bool;
+    fn attackers_to(&self, board: &mut Board<T, N>, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T>;
     fn play(&self, board: &mut Board<T, N>, act: Action);
 
     fn display_action(&self, board: &mut Board<T, N>, act: Action) -> Vec<String>;
 
     fn is_legal(&self, board: &mut Board<T, N>) -> bool;
     fn game_state(&self, board: &mut Board<T, N>, legal_actions: &[Action]) -> GameState;
+    /// The squares `piece` of `team` can never stand on, like pawns on the back ranks in chess.
+    fn unplaceable(&self, game: &Game<T, N>, piece: usize, team: Team) -> BitBoard<T>;
     fn gen_zobrist(&self, board: &mut Board<T, N>, seed: u64) -> ZobristTable;
     fn hash(&self, board: &mut Board<T, N>, table: &ZobristTable) -> u64;
 }
@@ -115,7 +238,12 @@ impl Team {
 pub struct Board<'a, T : BitInt, const N: usize> {
     pub game: &'a Game<T, N>,
     pub state: BoardState<T, N>,
-    pub history: Vec<ActionRecord>
+    pub history: Vec<ActionRecord>,
+    /// How many moves were played before the loaded position, which `history` doesn't hold.
+    pub earlier_moves: usize,
+    /// The positions before each action of `history`, for rules that look back at them (like sennichite in shogi).
+    /// Only rules that need them push them in `GameRules::play`, and `restore` trims them back to `history`.
+    pub positions: Vec<BoardState<T, N>>
 }
 
 #[derive(Clone)]
@@ -124,7 +252,14 @@ pub struct BoardState<T : BitInt, const N: usize> {
     pub first_move: BitBoard<T>,
     pub white: BitBoard<T>,
     pub black: BitBoard<T>,
-    pub pieces: [ BitBoard<T>; N ]
+    pub pieces: [ BitBoard<T>; N ],
+    /// Pieces that got here by promoting, which are demoted when captured into a pocket.
+    /// Only tracked in games with drops.
+    pub promoted: BitBoard<T>,
+    /// Indexed by team, then by piece type; how many of each piece type a team has in its pocket to drop.
+    pub pockets: [ [ u8; N ]; 2 ],
+    /// Plies since the last capture or pawn move, for games that count them (like chess's halfmove clock).
+    pub halfmove_clock: u16
 }
 
 impl<T : BitInt, const N: usize> BoardState<T, N> {
@@ -134,7 +269,10 @@ impl<T : BitInt, const N: usize> BoardState<T, N> {
             black: BitBoard::default(),
             white: BitBoard::default(),
             first_move: BitBoard::default(),
-            pieces: [ BitBoard::default(); N ]
+            pieces: [ BitBoard::default(); N ],
+            promoted: BitBoard::default(),
+            pockets: [ [ 0; N ]; 2 ],
+            halfmove_clock: 0
         }
     }
 
@@ -166,6 +304,72 @@ impl<T : BitInt, const N: usize> BoardState<T, N> {
         None
     }
 
+    /// Checks that the bitboards describe a consistent position, reporting every violated invariant.
+    pub fn validate(&self) -> Result<(), Vec<InvariantViolation<T>>> {
+        let mut violations = vec![];
+
+        let occupied = self.white.or(self.black);
+
+        let overlap = self.white.and(self.black);
+        if overlap.set() {
+            violations.push(InvariantViolation::TeamsOverlap(overlap));
+        }
+
+        let mut typed = BitBoard::default();
+        for first in 0..N {
+            for second in (first + 1)..N {
+                let overlap = self.pieces[first].and(self.pieces[second]);
+                if overlap.set() {
+                    violations.push(InvariantViolation::PiecesOverlap { first, second, squares: overlap });
+                }
+            }
+
+            typed = typed.or(self.pieces[first]);
+        }
+
+        let untyped = occupied.and_not(typed);
+        if untyped.set() {
+            violations.push(InvariantViolation::UntypedSquares(untyped));
+        }
+
+        let unowned = typed.and_not(occupied);
+        if unowned.set() {
+            violations.push(InvariantViolation::UnownedPieces(unowned));
+        }
+
+        let empty_first_moves = self.first_move.and_not(occupied);
+        if empty_first_moves.set() {
+            violations.push(InvariantViolation::EmptyFirstMoves(empty_first_moves));
+        }
+
+        let empty_promotions = self.promoted.and_not(occupied);
+        if empty_promotions.set() {
+            violations.push(InvariantViolation::EmptyPromotions(empty_promotions));
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+/// A broken invariant of a `BoardState`, alongside the squares breaking it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantViolation<T : BitInt> {
+    /// Squares occupied by both `white` and `black`.
+    TeamsOverlap(BitBoard<T>),
+    /// Squares occupied by two piece types at once.
+    PiecesOverlap { first: usize, second: usize, squares: BitBoard<T> },
+    /// Squares occupied by a team, but by no piece type.
+    UntypedSquares(BitBoard<T>),
+    /// Squares occupied by a piece type, but by no team.
+    UnownedPieces(BitBoard<T>),
+    /// Squares marked in `first_move` that have no piece on them.
+    EmptyFirstMoves(BitBoard<T>),
+    /// Squares marked in `promoted` that have no piece on them.
+    EmptyPromotions(BitBoard<T>)
 }
 
 impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
@@ -173,7 +377,9 @@ impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
         Self {
             game,
             state: BoardState::new(),
-            history: vec![]
+            history: vec![],
+            earlier_moves: 0,
+            positions: vec![]
         }
     }
 
@@ -182,7 +388,7 @@ impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
     }
 
     pub fn load_pieces(&mut self, pos: &str) {
-        let piece_map = self.game.rules.piece_map();
+        let piece_map = self.game.piece_map();
         for (y, row) in pos.split("/").enumerate() {
             let y = y as u16;
             let mut x: u16 = 0;
@@ -193,6 +399,14 @@ impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
                     continue;
                 }
 
+                // A `~` marks the piece before it as promoted, like in Crazyhouse FENs.
+                // Only games with drops track promoted pieces, so no other game can load them.
+                if char == '~' && x > 0 {
+                    assert!(self.game.drops.enabled(), "Promoted pieces (`~`) need a game with drops");
+                    self.state.promoted = self.state.promoted.or(BitBoard::coords(x - 1, y, self.game.bounds));
+                    continue;
+                }
+
                 let matched_piece = piece_map.iter().position(|&n| n == char.to_ascii_lowercase());
 
                 if let Some(index) = matched_piece {
@@ -239,6 +453,21 @@ impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
         self.game.rules.attacks(self, mask)
     }
 
+    /// Whether any piece of `team` can see `mask`, whichever team is to move.
+    pub fn attacked_by(&mut self, mask: BitBoard<T>, team: Team) -> bool {
+        let moving_team = self.state.moving_team;
+        self.state.moving_team = team;
+        let attacked = self.attacks(mask);
+        self.state.moving_team = moving_team;
+
+        attacked
+    }
+
+    /// The pieces of `team` which attack `square`, with `occupancy` as the blockers.
+    pub fn attackers_to(&mut self, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        self.game.rules.attackers_to(self, square, team, occupancy)
+    }
+
     pub fn game_state(&mut self, actions: &[Action]) -> GameState {
         self.game.rules.game_state(self, actions)
     }
@@ -280,12 +509,26 @@ impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
 
         self.state.moving_team = state.moving_team.next();
         self.history.push(ActionRecord::Action(action));
+
+        #[cfg(feature = "validate")]
+        self.assert_valid(|| format!("playing {:?}", action));
+
         state
     }
 
     pub fn restore(&mut self, state: BoardState<T, N>) {
         self.state = state;
         self.history.pop();
+        self.positions.truncate(self.history.len());
+
+        #[cfg(feature = "validate")]
+        self.assert_valid(|| "restoring".to_string());
+    }
 
+    #[cfg(feature = "validate")]
+    fn assert_valid(&self, context: impl Fn() -> String) {
+        if let Err(violations) = self.state.validate() {
+            panic!("Board invariants broken after {}: {:?}", context(), violations);
+        }
     }
 }
\ No newline at end of file
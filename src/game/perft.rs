@@ -1,24 +1,72 @@
+use std::{sync::atomic::{AtomicUsize, Ordering}, thread};
 
 use crate::bitboard::BitInt;
 
-use super::Board;
+use super::{action::Action, zobrist::ZobristTable, Board};
+
+#[derive(Clone, Copy, Default)]
+struct PerftEntry {
+    hash: u64,
+    depth: usize,
+    nodes: usize
+}
+
+/// A transposition table for perft, caching node counts by `(hash, depth)`.
+///
+/// Hashes come from the game's own `GameRules::hash`, so any game with a Zobrist implementation can use it.
+pub struct PerftTable {
+    zobrist: ZobristTable,
+    entries: Vec<PerftEntry>
+}
+
+impl PerftTable {
+    /// Creates a table with `2^bits` entries, generating a Zobrist table for the board's game.
+    pub fn new<T : BitInt, const N: usize>(board: &mut Board<T, N>, bits: usize) -> PerftTable {
+        let zobrist = board.game.rules.gen_zobrist(board, 0xC0FFEE);
+        PerftTable {
+            zobrist,
+            entries: vec![ PerftEntry::default(); 1 << bits ]
+        }
+    }
+
+    #[inline(always)]
+    fn slot(&self, hash: u64, depth: usize) -> usize {
+        // Mixing the depth in keeps the same position at different depths from always fighting over one slot.
+        let key = hash ^ (depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        (key as usize) & (self.entries.len() - 1)
+    }
+
+    fn get(&self, hash: u64, depth: usize) -> Option<usize> {
+        let entry = self.entries[self.slot(hash, depth)];
+        if entry.hash == hash && entry.depth == depth {
+            Some(entry.nodes)
+        } else {
+            None
+        }
+    }
+
+    fn insert(&mut self, hash: u64, depth: usize, nodes: usize) {
+        let slot = self.slot(hash, depth);
+        self.entries[slot] = PerftEntry { hash, depth, nodes };
+    }
+}
 
 impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
     pub fn perft(&mut self, depth: usize) -> usize {
         if depth == 0 { return 1; }
-    
+
         let actions = self.actions();
-    
+
         let mut nodes = 0;
         for action in actions {
             let state = self.play(action);
             let is_legal = self.game.rules.is_legal(self);
-    
+
             if !is_legal {
                 self.restore(state);
                 continue;
             }
-    
+
             let sub_nodes = self.perft(depth - 1);
             self.restore(state);
 
@@ -27,36 +75,155 @@ impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
         nodes
     }
 
-    pub fn perft_debug(&mut self, depth: usize) -> usize {
+    /// Perft that counts legal actions at depth 1 instead of playing them out,
+    /// and caches the node counts of subtrees in `table`.
+    pub fn perft_hashed(&mut self, depth: usize, table: &mut PerftTable) -> usize {
         if depth == 0 { return 1; }
-    
+        if depth == 1 { return self.legals().len(); }
+
+        let hash = self.game.rules.hash(self, &table.zobrist);
+        if let Some(nodes) = table.get(hash, depth) {
+            return nodes;
+        }
+
         let actions = self.actions();
-        let mut lines: Vec<String> = vec![];
-    
+
         let mut nodes = 0;
         for action in actions {
             let state = self.play(action);
             let is_legal = self.game.rules.is_legal(self);
-    
+
             if !is_legal {
                 self.restore(state);
                 continue;
             }
-    
-            let sub_nodes = self.perft(depth - 1);
-            self.restore(state);
 
-            lines.push(format!("{} - {}", self.display_uci_action(action), sub_nodes));
+            let sub_nodes = self.perft_hashed(depth - 1, table);
+            self.restore(state);
 
             nodes += sub_nodes;
         }
 
+        table.insert(hash, depth, nodes);
+        nodes
+    }
+
+    /// Lists every legal root action alongside the perft of the position it leads to.
+    pub fn divide(&mut self, depth: usize) -> Vec<(Action, usize)> {
+        if depth == 0 { return vec![]; }
+
+        let actions = self.actions();
+        let mut divide = Vec::with_capacity(actions.len());
+
+        for action in actions {
+            let state = self.play(action);
+            let is_legal = self.game.rules.is_legal(self);
+
+            if !is_legal {
+                self.restore(state);
+                continue;
+            }
+
+            let sub_nodes = self.perft(depth - 1);
+            self.restore(state);
+
+            divide.push((action, sub_nodes));
+        }
+
+        divide
+    }
+
+    /// Same as `divide`, but with actions in UCI notation and sorted by it.
+    pub fn divide_uci(&mut self, depth: usize) -> Vec<(String, usize)> {
+        let divide = self.divide(depth);
+        let mut lines: Vec<(String, usize)> = divide.into_iter()
+            .map(|(action, nodes)| (self.display_uci_action(action), nodes))
+            .collect();
+
         lines.sort();
+        lines
+    }
+
+    /// Perft that splits the legal root actions across `threads` worker threads.
+    ///
+    /// Each worker owns a clone of the board and pulls root actions from a shared counter,
+    /// so uneven subtrees don't leave threads idle.
+    pub fn perft_parallel(&mut self, depth: usize, threads: usize) -> usize {
+        if depth <= 1 || threads <= 1 {
+            return self.perft(depth);
+        }
+
+        let roots = self.legals();
+        let next = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.min(roots.len()))
+                .map(|_| {
+                    let mut board = self.clone();
+                    let roots = &roots;
+                    let next = &next;
+
+                    scope.spawn(move || {
+                        let mut nodes = 0;
+                        loop {
+                            let index = next.fetch_add(1, Ordering::Relaxed);
+                            let Some(&action) = roots.get(index) else { break };
 
-        for line in lines {
-            println!("{}", line);
+                            let state = board.play(action);
+                            nodes += board.perft(depth - 1);
+                            board.restore(state);
+                        }
+                        nodes
+                    })
+                })
+                .collect();
+
+            workers.into_iter()
+                .map(|worker| worker.join().expect("Perft worker panicked"))
+                .sum()
+        })
+    }
+
+    pub fn perft_debug(&mut self, depth: usize) -> usize {
+        if depth == 0 { return 1; }
+
+        let lines = self.divide_uci(depth);
+
+        let mut nodes = 0;
+        for (action, sub_nodes) in lines {
+            println!("{} - {}", action, sub_nodes);
+            nodes += sub_nodes;
         }
 
         nodes
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{Chess, MagicMoves}, game::GameTemplate};
+
+    use super::PerftTable;
+
+    #[test]
+    fn parallel_perft_matches() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+
+        for pos in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"
+        ] {
+            let mut board = chess.load(pos);
+            assert_eq!(board.perft_parallel(3, 4), board.perft(3));
+        }
+    }
+
+    #[test]
+    fn hashed_perft_matches() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.load("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let mut table = PerftTable::new(&mut board, 16);
+
+        assert_eq!(board.perft_hashed(4, &mut table), 4085603);
+    }
+}
@@ -0,0 +1,258 @@
+use crate::bitboard::{BitBoard, BitInt};
+
+use super::{action::{make_chess_move, Action}, promotion::{is_promotion, make_promotion_move}, Board, Castling, Game, Team};
+
+/// `PieceRules` handles a single piece type's behavior.
+///
+/// Pieces are registered on a `Game` in `Game::pieces`, where their position in the registry is their piece index.
+pub trait PieceRules<T : BitInt, const N: usize> : Send + Sync {
+    /// Called once after the game is created, to precompute lookups for the piece.
+    /// While processing, `game.pieces` is empty, so other pieces can't be looked up yet.
+    fn process(&mut self, _game: &mut Game<T, N>, _piece_index: usize) {}
+
+    fn add_actions(&self, board: &mut Board<T, N>, actions: &mut Vec<Action>, piece_index: usize);
+
+    /// Whether any piece of this type on the moving team can see `mask`.
+    fn attacks(&self, board: &mut Board<T, N>, piece_index: usize, mask: BitBoard<T>) -> bool;
+
+    /// The pieces of this type on `team` which attack `square`, with `occupancy` as the blockers.
+    /// Only pieces within `occupancy` can attack, so removing a piece from it (like for SEE) also stops it attacking.
+    fn attackers_to(&self, board: &mut Board<T, N>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T>;
+
+    /// The lines pieces of this type on `team` could attack `square` along, traced out from `square`
+    /// up to the first piece of `occupancy` on each (or the second, for hoppers), which are the only pieces that can block them.
+    /// Lines are traced with the moves of the other team, so directional pieces are assumed to be symmetric left to right.
+    /// Pieces that can't be blocked (like leapers) have none.
+    fn lines(&self, _board: &mut Board<T, N>, _piece_index: usize, _square: u16, _team: Team, _occupancy: BitBoard<T>) -> BitBoard<T> {
+        BitBoard::default()
+    }
+
+    /// Every square a piece of this type on `team` could attack from `square` on some board, used to skip piece types
+    /// which can't reach a square when looking for attackers. Defaults to the whole board, which never skips the piece.
+    fn reach(&self, _game: &Game<T, N>, _piece_index: usize, _square: u16, _team: Team) -> BitBoard<T> {
+        BitBoard::default().not()
+    }
+
+    /// Applies an action made with this piece. Defaults to moving the piece and capturing whatever is on `action.to`.
+    fn make_move(&self, board: &mut Board<T, N>, action: Action) {
+        make_chess_move(&mut board.state, action);
+    }
+
+    /// The square whose piece (if any) `action` captures, or `None` if it can't capture at all.
+    /// Defaults to `action.to`, like `make_move`.
+    fn captured_square(&self, _board: &Board<T, N>, action: Action) -> Option<u16> {
+        Some(action.to)
+    }
+}
+
+pub struct Piece<T : BitInt, const N: usize> {
+    pub name: String,
+    pub symbol: char,
+    pub rules: Box<dyn PieceRules<T, N>>
+}
+
+impl<T : BitInt, const N: usize> Piece<T, N> {
+    pub fn new(name: &str, symbol: char, rules: impl PieceRules<T, N> + 'static) -> Piece<T, N> {
+        Piece {
+            name: name.to_string(),
+            symbol,
+            rules: Box::new(rules)
+        }
+    }
+}
+
+impl<T : BitInt, const N: usize> Game<T, N> {
+    pub fn find_piece(&self, name: &str) -> Option<usize> {
+        self.pieces.iter().position(|piece| piece.name == name)
+    }
+
+    /// The symbol of each registered piece, by piece type, which positions are written with.
+    pub fn piece_map(&self) -> Vec<char> {
+        self.pieces.iter().map(|piece| piece.symbol).collect()
+    }
+
+    /// Runs `PieceRules::process` for every registered piece, after finding the pieces that castle,
+    /// then fills in `Game::reach` from `PieceRules::reach`.
+    pub fn process_pieces(&mut self) {
+        self.castling = self.find_piece("king").zip(self.find_piece("rook"))
+            .map(|(king, rook)| Castling { king, rook });
+
+        let mut pieces = std::mem::take(&mut self.pieces);
+        for (piece_index, piece) in pieces.iter_mut().enumerate() {
+            piece.rules.process(self, piece_index);
+        }
+        self.pieces = pieces;
+
+        let squares = (self.bounds.rows * self.bounds.cols) as usize;
+        self.reach = self.pieces.iter()
+            .enumerate()
+            .map(|(piece_index, piece)| {
+                let mut reach = vec![BitBoard::default(); squares];
+                for team in [ Team::White, Team::Black ] {
+                    for from in 0..squares {
+                        for square in piece.rules.reach(self, piece_index, from as u16, team).iter() {
+                            if let Some(attackers) = reach.get_mut(square as usize) {
+                                *attackers = attackers.or(BitBoard::index(from as u16));
+                            }
+                        }
+                    }
+                }
+                reach
+            })
+            .collect();
+    }
+
+    /// The squares a piece of type `piece_index` on either team could attack some square of `mask` from, following `Game::reach`.
+    #[inline(always)]
+    pub fn reach(&self, piece_index: usize, mask: BitBoard<T>) -> BitBoard<T> {
+        match self.reach.get(piece_index) {
+            Some(reach) => mask.iter().fold(BitBoard::default(), |from, square| from.or(reach[square as usize])),
+            None => BitBoard::default().not()
+        }
+    }
+}
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    pub fn find_piece(&self, name: &str) -> Option<usize> {
+        self.game.find_piece(name)
+    }
+
+    /// Lists the actions of every registered piece, with promotions following `Game::promotion`.
+    pub fn piece_actions(&mut self) -> Vec<Action> {
+        let game = self.game;
+        let promotion = &game.promotion;
+        let mut actions = Vec::with_capacity(50);
+
+        for (piece_index, piece) in game.pieces.iter().enumerate() {
+            let start = actions.len();
+            piece.rules.add_actions(self, &mut actions, piece_index);
+
+            if promotion.promotion(piece_index).is_some() {
+                let team = self.state.moving_team;
+                if actions[start..].iter().any(|&action| promotion.can_promote(action, team)) {
+                    for action in actions.split_off(start) {
+                        promotion.add_promotions(&self.state, &mut actions, action);
+                    }
+                }
+            }
+        }
+
+        if game.drops.enabled() {
+            game.drops.add_drops(&self.state, &mut actions);
+        }
+
+        actions
+    }
+
+    /// Whether any registered piece on the moving team can see `mask`, skipping piece types which can't reach it.
+    pub fn piece_attacks(&mut self, mask: BitBoard<T>) -> bool {
+        let game = self.game;
+        let team = self.state.team_to_move();
+        game.pieces.iter()
+            .enumerate()
+            .any(|(piece_index, piece)| {
+                self.state.pieces[piece_index].and(team).and(game.reach(piece_index, mask)).set()
+                    && piece.rules.attacks(self, piece_index, mask)
+            })
+    }
+
+    /// The pieces of every registered piece type on `team` which attack `square`, with `occupancy` as the blockers,
+    /// skipping piece types which can't reach it.
+    pub fn piece_attackers_to(&mut self, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        let game = self.game;
+        let pieces = self.state.team(team);
+        let mask = BitBoard::index(square);
+
+        let mut attackers = BitBoard::default();
+
+        for (piece_index, piece) in game.pieces.iter().enumerate() {
+            if self.state.pieces[piece_index].and(pieces).and(game.reach(piece_index, mask)).set() {
+                attackers = attackers.or(piece.rules.attackers_to(self, piece_index, square, team, occupancy));
+            }
+        }
+
+        attackers
+    }
+
+    /// The lines every registered piece type on `team` could attack `square` along, for piece types `team` has on the board.
+    pub fn piece_lines(&mut self, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        let game = self.game;
+        let pieces = self.state.team(team);
+
+        let mut lines = BitBoard::default();
+
+        for (piece_index, piece) in game.pieces.iter().enumerate() {
+            if self.state.pieces[piece_index].and(pieces).set() {
+                lines = lines.or(piece.rules.lines(self, piece_index, square, team, occupancy));
+            }
+        }
+
+        lines
+    }
+
+    /// Plays an action through the rules of the piece making it, or promotes (or drops) the piece if it's a promotion (or a drop).
+    /// In games with drops, captured pieces are put into the moving team's pocket.
+    pub fn make_piece_move(&mut self, action: Action) {
+        let game = self.game;
+        if action.is_drop() {
+            game.drops.make_drop_move(&mut self.state, action);
+            return;
+        }
+
+        if game.drops.enabled() {
+            self.make_pocket_move(action);
+        } else {
+            self.make_board_move(action);
+        }
+    }
+
+    /// Plays an action which isn't a drop in a game with drops, putting the piece it captures into the moving team's pocket.
+    fn make_pocket_move(&mut self, action: Action) {
+        let enemies = self.state.opposite_team();
+        let captured = self.game.pieces[action.piece as usize].rules.captured_square(self, action)
+            .filter(|&square| enemies.and(BitBoard::index(square)).set())
+            .map(|square| (square, self.state.piece_at(square).expect("Captured square has a piece")));
+
+        self.make_board_move(action);
+        self.pocket_capture(action, captured);
+    }
+
+    /// Plays an action which isn't a drop, promoting the piece if it's a promotion.
+    fn make_board_move(&mut self, action: Action) {
+        if is_promotion(self, action) {
+            make_promotion_move(&mut self.state, action);
+        } else {
+            self.game.pieces[action.piece as usize].rules.make_move(self, action);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{pieces::{betza::Betza, sliders::{hopper::{Hop, Hopper}, rook::RookMoves}, testing::{chess_variant, square, squares}}, KNIGHT, PAWN, QUEEN, ROOK}, game::piece::Piece};
+
+    #[test]
+    fn reach_covers_every_attacker() {
+        let game = chess_variant(vec![]);
+        let e4 = squares(8, &[ "e4" ]);
+
+        // Pawns attack e4 from diagonally behind it on either side, and knights from a leap away.
+        assert_eq!(game.reach(PAWN, e4), squares(8, &[ "d3", "f3", "d5", "f5" ]));
+        assert_eq!(game.reach(KNIGHT, e4), squares(8, &[ "c3", "c5", "d2", "d6", "f2", "f6", "g3", "g5" ]));
+
+        // Rooks reach along the whole rank and file, as any square between could be empty,
+        // and a mask of several squares is reached from any of their lines (which a8 and h1 share).
+        assert_eq!(game.reach(ROOK, e4).count(), 14);
+        assert_eq!(game.reach(ROOK, squares(8, &[ "a1", "h8" ])).count(), 26);
+
+        // A cannon only captures over a screen, but lands along its rays, so it's still reached from the whole rank and file.
+        let hopper = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Hopper::new(RookMoves, Hop::Cannon))) ]);
+        let betza = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Betza::new("mRcpR").expect("Valid notation"))) ]);
+        for game in [ hopper, betza ] {
+            let reach = game.reach(QUEEN, e4);
+            assert_eq!(reach.count(), 14);
+            assert!(reach.and(squares(8, &[ "e8" ])).set());
+            assert_eq!(reach.bitscan_forward() as usize, square(8, "e1"));
+        }
+    }
+}
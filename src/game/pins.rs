@@ -0,0 +1,128 @@
+use crate::bitboard::{BitBoard, BitInt};
+
+use super::{Board, Team};
+
+/// Attacks on some target squares which are only blocked by a single piece.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XRays<T : BitInt> {
+    /// The pieces which, once removed, let new attacks through onto the targets.
+    pub blockers: BitBoard<T>,
+    /// The pieces whose attacks onto the targets are blocked by `blockers`.
+    pub attackers: BitBoard<T>
+}
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    /// Finds the pieces among `blockers` that stand between `targets` and the pieces of `team`.
+    ///
+    /// Only the nearest piece to each target along the lines of `team`'s pieces (see `PieceRules::lines`) can be a blocker,
+    /// so each of those is taken out of the occupancy in turn, and any attackers of `team` it reveals are x-rays.
+    /// Since this goes through `attackers_to`, it works for any slider (or hopper) registered on the game.
+    pub fn xrays(&mut self, targets: BitBoard<T>, team: Team, blockers: BitBoard<T>) -> XRays<T> {
+        let occupancy = self.state.white.or(self.state.black);
+        let mut xrays = XRays { blockers: BitBoard::default(), attackers: BitBoard::default() };
+
+        for target in targets.iter() {
+            let target = target as u16;
+            let candidates = self.piece_lines(target, team, occupancy)
+                .and(blockers)
+                .and(occupancy)
+                .and_not(BitBoard::index(target));
+
+            if candidates.empty() {
+                continue;
+            }
+
+            let attackers = self.attackers_to(target, team, occupancy);
+
+            for blocker in candidates.iter() {
+                let blocker = BitBoard::index(blocker as u16);
+                let revealed = self.attackers_to(target, team, occupancy.and_not(blocker)).and_not(attackers);
+
+                if revealed.set() {
+                    xrays.blockers = xrays.blockers.or(blocker);
+                    xrays.attackers = xrays.attackers.or(revealed);
+                }
+            }
+        }
+
+        xrays
+    }
+
+    /// The pieces of `team` which can't move away without exposing one of its royal pieces.
+    pub fn pinned(&mut self, team: Team) -> BitBoard<T> {
+        self.pins(team).blockers
+    }
+
+    /// The enemy pieces pinning pieces of `team` to its royal pieces.
+    pub fn pinners(&mut self, team: Team) -> BitBoard<T> {
+        self.pins(team).attackers
+    }
+
+    /// The pieces of `team` which would attack an enemy royal piece by moving out of the way of another piece of `team`.
+    pub fn discovered_candidates(&mut self, team: Team) -> BitBoard<T> {
+        let royals = self.game.royalty.royals(self, team.next());
+        self.xrays(royals, team, self.state.team(team)).blockers
+    }
+
+    fn pins(&mut self, team: Team) -> XRays<T> {
+        let royals = self.game.royalty.royals(self, team);
+        self.xrays(royals, team.next(), self.state.team(team))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::BitBoard, chess::{pieces::{betza::Betza, sliders::{hopper::{Hop, Hopper}, rook::RookMoves}, testing::chess_variant}, Chess, MagicMoves, SliderMoves, KNIGHT, QUEEN}, game::{action::square_to_index, piece::Piece, GameTemplate, Team}};
+
+    fn squares(names: &[&str]) -> BitBoard<u64> {
+        names.iter().fold(BitBoard::default(), |board, name| board.or(BitBoard::index(square_to_index(name).expect("Valid square"))))
+    }
+
+    #[test]
+    fn chess_pins() {
+        for chess in [ Chess::<MagicMoves>::create::<u64, 6>(), Chess::<SliderMoves>::create::<u64, 6>() ] {
+            // The rook pins the e2 knight, and the bishop pins the b4 pawn.
+            let mut board = chess.load("4r2k/8/8/b7/1P6/8/4N3/4K3 w - - 0 1");
+            assert_eq!(board.pinned(Team::White), squares(&[ "b4", "e2" ]));
+            assert_eq!(board.pinners(Team::White), squares(&[ "a5", "e8" ]));
+            assert!(board.pinned(Team::Black).empty());
+
+            // Two pieces on the same line shield the king together, so neither is pinned.
+            let mut board = chess.load("4r2k/8/8/8/4P3/8/4N3/4K3 w - - 0 1");
+            assert!(board.pinned(Team::White).empty());
+
+            // Moving the d4 knight off the diagonal uncovers the bishop's attack on the king.
+            let mut board = chess.load("7k/8/8/8/3N4/8/1B6/K7 w - - 0 1");
+            assert_eq!(board.discovered_candidates(Team::White), squares(&[ "d4" ]));
+            assert!(board.discovered_candidates(Team::Black).empty());
+        }
+    }
+
+    #[test]
+    fn hopper_pins() {
+        let hopper = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Hopper::new(RookMoves, Hop::Cannon))) ]);
+        let betza = chess_variant(vec![ (QUEEN, Piece::new("queen", 'q', Betza::new("mRcpR").expect("Valid notation"))) ]);
+
+        for game in [ hopper, betza ] {
+            // The cannon on e8 can't capture the king over both e-file pieces,
+            // but either of them leaving would leave it a screen to capture over.
+            let mut board = game.load("4q2k/8/8/8/4P3/8/4N3/4K3 w - - 0 1");
+            assert_eq!(board.pinned(Team::White), squares(&[ "e2", "e4" ]));
+            assert_eq!(board.pinners(Team::White), squares(&[ "e8" ]));
+
+            // With a single screen, the cannon already attacks the king, and removing the screen doesn't pin anything.
+            let mut board = game.load("4q2k/8/8/8/8/8/4N3/4K3 w - - 0 1");
+            assert!(board.pinned(Team::White).empty());
+        }
+    }
+
+    #[test]
+    fn rider_pins() {
+        let game = chess_variant(vec![ (KNIGHT, Piece::new("knight", 'n', Betza::new("NN").expect("Valid notation"))) ]);
+
+        // The nightrider on b7 pins the c5 bishop to the e1 king, but the g1 rook off its line isn't pinned.
+        let mut board = game.load("7k/1n6/8/2B5/8/8/8/4K1R1 w - - 0 1");
+        assert_eq!(board.pinned(Team::White), squares(&[ "c5" ]));
+        assert_eq!(board.pinners(Team::White), squares(&[ "b7" ]));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::bitboard::BitInt;
+
+use super::{action::Action, Board, BoardState, Game, GameState};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlayoutOptions {
+    pub seed: u64,
+    /// Stops the playout after this many plies, leaving the game `Ongoing`.
+    /// Chess has no draw rules (like the fifty move rule or repetitions) to end a game otherwise, so without it a playout can run for a very long time.
+    pub max_ply: Option<usize>
+}
+
+#[derive(Debug, Clone)]
+pub struct Playout {
+    pub state: GameState,
+    pub actions: Vec<Action>
+}
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    fn play_randomly(
+        &mut self,
+        options: PlayoutOptions,
+        mut filter: impl FnMut(&mut Board<'a, T, N>, Action) -> bool
+    ) -> (Playout, Vec<BoardState<T, N>>) {
+        let mut rng = fastrand::Rng::with_seed(options.seed);
+        let mut actions = vec![];
+        let mut states = vec![];
+
+        loop {
+            let legals = self.legals();
+            let state = self.game_state(&legals);
+
+            let cut_off = options.max_ply.is_some_and(|max_ply| actions.len() >= max_ply);
+            if !matches!(state, GameState::Ongoing) || cut_off {
+                return (Playout { state, actions }, states);
+            }
+
+            let candidates: Vec<Action> = legals.into_iter()
+                .filter(|&action| filter(self, action))
+                .collect();
+
+            // The filter rejected every legal action, so the playout can't go on.
+            if candidates.is_empty() {
+                return (Playout { state, actions }, states);
+            }
+
+            let action = candidates[rng.usize(..candidates.len())];
+
+            states.push(self.play(action));
+            actions.push(action);
+        }
+    }
+
+    /// Plays random legal actions until the game ends or `max_ply` is reached, then restores the board.
+    pub fn playout(&mut self, options: PlayoutOptions) -> Playout {
+        self.playout_filtered(options, |_, _| true)
+    }
+
+    /// Same as `playout`, but only picks actions accepted by `filter`.
+    /// If `filter` rejects every legal action, the playout stops there, leaving the game `Ongoing`.
+    pub fn playout_filtered(
+        &mut self,
+        options: PlayoutOptions,
+        filter: impl FnMut(&mut Board<'a, T, N>, Action) -> bool
+    ) -> Playout {
+        let (playout, states) = self.play_randomly(options, filter);
+
+        for state in states.into_iter().rev() {
+            self.restore(state);
+        }
+
+        playout
+    }
+}
+
+impl<T : BitInt, const N: usize> Game<T, N> {
+    /// Plays a random game from the default position, returning the board in its final position.
+    /// Nothing but `max_ply` bounds how long the game runs, so it should be set.
+    pub fn random_game(&self, options: PlayoutOptions) -> (Board<'_, T, N>, Playout) {
+        let mut board = self.default();
+        let (playout, _) = board.play_randomly(options, |_, _| true);
+        (board, playout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{Chess, MagicMoves, PAWN}, game::{GameState, GameTemplate}};
+
+    use super::PlayoutOptions;
+
+    #[test]
+    fn playouts_are_seeded() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.default();
+        let options = PlayoutOptions { seed: 42, max_ply: Some(300) };
+
+        let first = board.playout(options);
+        let second = board.playout(options);
+
+        assert_eq!(first.actions, second.actions);
+        assert!(board.history.is_empty());
+
+        let (played, playout) = chess.random_game(options);
+        assert_eq!(playout.actions, first.actions);
+        assert_eq!(played.history.len(), playout.actions.len());
+    }
+
+    #[test]
+    fn playout_cutoff() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.default();
+
+        let playout = board.playout(PlayoutOptions { seed: 7, max_ply: Some(4) });
+        assert_eq!(playout.actions.len(), 4);
+        assert!(matches!(playout.state, GameState::Ongoing));
+    }
+
+    #[test]
+    fn playout_filter() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.default();
+
+        // Only ever push pawns while possible.
+        let playout = board.playout_filtered(PlayoutOptions { seed: 3, max_ply: Some(6) }, |board, action| {
+            board.piece_at(action.from) == Some(PAWN)
+        });
+
+        assert!(playout.actions.iter().all(|action| action.piece as usize == PAWN));
+    }
+
+    #[test]
+    fn playout_filter_rejecting_everything() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let mut board = chess.default();
+
+        let playout = board.playout_filtered(PlayoutOptions { seed: 3, max_ply: Some(6) }, |_, _| false);
+
+        assert!(playout.actions.is_empty());
+        assert!(matches!(playout.state, GameState::Ongoing));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::bitboard::{BitBoard, BitInt, Bounds};
+
+use super::{action::Action, Board, BoardState, Team};
+
+/// How a single piece type promotes.
+#[derive(Clone, Debug)]
+pub struct Promotion<T : BitInt> {
+    /// The piece types it can promote into, in the order their actions are listed.
+    pub targets: Vec<usize>,
+    /// Indexed by team; the squares where promotion is mandatory.
+    /// Elsewhere in the promotion zone, the piece may also stay as it is.
+    pub forced: [BitBoard<T>; 2]
+}
+
+/// A game's promotion rules.
+///
+/// Promotions are listed by `Board::piece_actions` for every piece type with a `Promotion`,
+/// as actions with an `info` of `2 + target` (so promotions don't clash with an info of 0 or 1, like en passant).
+#[derive(Clone, Debug)]
+pub struct PromotionRules<T : BitInt> {
+    bounds: Bounds,
+    /// Indexed by team; the squares where pieces can promote.
+    pub zones: [BitBoard<T>; 2],
+    /// Whether moves starting in the zone can promote too (like in shogi), rather than only moves ending in it.
+    pub from_zone: bool,
+    /// Indexed by piece type; how each piece type promotes, if it can.
+    pub pieces: Vec<Option<Promotion<T>>>,
+    /// Indexed by piece type; the most pieces of that type a team can have for a promotion into it to be allowed.
+    /// Grand Chess only allows promoting into lost pieces, by limiting each type to its starting count.
+    pub limits: Vec<Option<u32>>
+}
+
+impl<T : BitInt> PromotionRules<T> {
+    /// Promotions in the last `zone_ranks` ranks of each team, for no piece types yet.
+    pub fn new(bounds: Bounds, zone_ranks: u16) -> PromotionRules<T> {
+        PromotionRules {
+            bounds,
+            zones: [ BitBoard::edges_up(bounds, zone_ranks), BitBoard::edges_down(bounds, zone_ranks) ],
+            from_zone: false,
+            pieces: vec![],
+            limits: vec![]
+        }
+    }
+
+    /// Lets `piece` promote into `targets`, which is mandatory in the last `forced_ranks` ranks.
+    /// A `forced_ranks` at least as large as the zone makes promotion mandatory, like for chess pawns.
+    pub fn promote(mut self, piece: usize, targets: Vec<usize>, forced_ranks: u16) -> PromotionRules<T> {
+        if self.pieces.len() <= piece {
+            self.pieces.resize(piece + 1, None);
+        }
+
+        let forced = [ BitBoard::edges_up(self.bounds, forced_ranks), BitBoard::edges_down(self.bounds, forced_ranks) ];
+        self.pieces[piece] = Some(Promotion { targets, forced });
+        self
+    }
+
+    /// Only allows promoting into `piece` while a team has fewer than `count` of them.
+    pub fn limit(mut self, piece: usize, count: u32) -> PromotionRules<T> {
+        if self.limits.len() <= piece {
+            self.limits.resize(piece + 1, None);
+        }
+
+        self.limits[piece] = Some(count);
+        self
+    }
+
+    /// Also lets moves starting in the zone promote.
+    pub fn from_zone(mut self) -> PromotionRules<T> {
+        self.from_zone = true;
+        self
+    }
+
+    /// The piece type `piece` was promoted from, found as the first piece type promoting into it (or `piece` itself, if none do).
+    pub fn demotion(&self, piece: usize) -> usize {
+        self.pieces.iter()
+            .position(|promotion| promotion.as_ref().is_some_and(|promotion| promotion.targets.contains(&piece)))
+            .unwrap_or(piece)
+    }
+
+    #[inline(always)]
+    pub fn promotion(&self, piece: usize) -> Option<&Promotion<T>> {
+        self.pieces.get(piece).and_then(|promotion| promotion.as_ref())
+    }
+
+    /// Whether `action`, made by `team`, can promote.
+    #[inline(always)]
+    pub fn can_promote(&self, action: Action, team: Team) -> bool {
+        let zone = self.zones[team.index()];
+        zone.and(BitBoard::index(action.to)).set() || (self.from_zone && zone.and(BitBoard::index(action.from)).set())
+    }
+
+    /// Adds `action`, or the promotions it can make instead.
+    /// Only plain actions (with an `info` of 0) can promote, so special moves like en passant keep their meaning.
+    pub fn add_promotions<const N: usize>(&self, state: &BoardState<T, N>, actions: &mut Vec<Action>, action: Action) {
+        let team = state.moving_team;
+        let promotion = match self.promotion(action.piece as usize) {
+            Some(promotion) if action.info == 0 && self.can_promote(action, team) => promotion,
+            _ => {
+                actions.push(action);
+                return;
+            }
+        };
+
+        if promotion.forced[team.index()].and(BitBoard::index(action.to)).empty() {
+            actions.push(action);
+        }
+
+        let pieces = state.team(team);
+        for &target in &promotion.targets {
+            let limited = self.limits.get(target)
+                .and_then(|&limit| limit)
+                .is_some_and(|limit| state.pieces[target].and(pieces).count() >= limit);
+
+            if !limited {
+                actions.push(action.with_info(2 + target as u8));
+            }
+        }
+    }
+}
+
+/// Whether `action` is a promotion under the game's promotion rules.
+#[inline(always)]
+pub fn is_promotion<T : BitInt, const N: usize>(board: &Board<T, N>, action: Action) -> bool {
+    action.info >= 2 && board.game.promotion.promotion(action.piece as usize).is_some()
+}
+
+/// Moves the promoting piece, capturing whatever is on `action.to`, and replaces it with the piece type `action.info - 2`.
+pub fn make_promotion_move<T: BitInt, const N: usize>(state: &mut BoardState<T, N>, action: Action) {
+    let piece_index = action.piece as usize;
+    let victim_index = state.piece_at(action.to);
+    let promoted_piece_type = (action.info - 2) as usize;
+
+    let from = BitBoard::index(action.from);
+    let to = BitBoard::index(action.to);
+
+    let team = state.moving_team;
+
+    if let Some(piece_type) = victim_index {
+        // Remove the captured piece type from its bitboard
+        state.pieces[piece_type] = state.pieces[piece_type].xor(to);
+
+        match team {
+            Team::White => state.black = state.black.xor(to),
+            Team::Black => state.white = state.white.xor(to)
+        }
+    }
+
+    // Remove the promoting piece, and add the new piece where it left.
+    state.pieces[piece_index] = state.pieces[piece_index].xor(from);
+    state.pieces[promoted_piece_type] = state.pieces[promoted_piece_type].or(to);
+
+    // Update the moved piece's team bitboard
+    match team {
+        Team::White => state.white = state.white.xor(from).or(to),
+        Team::Black => state.black = state.black.xor(from).or(to)
+    }
+
+    state.first_move = state.first_move.and_not(from.or(to));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::Bounds, chess::{Chess, SliderMoves, BISHOP, KNIGHT, PAWN, QUEEN, ROOK}, game::{Board, GameTemplate}};
+
+    use super::PromotionRules;
+
+    fn piece_moves(board: &mut Board<u64, 6>, piece: u8) -> Vec<String> {
+        let mut moves: Vec<String> = board.legals().into_iter()
+            .filter(|action| action.piece == piece)
+            .map(|action| board.display_uci_action(action))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn chess_promotions() {
+        let game = Chess::<SliderMoves>::create::<u64, 6>();
+
+        let mut board = game.load("8/P7/8/8/8/8/8/k6K w - - 0 1");
+        assert_eq!(piece_moves(&mut board, PAWN as u8), vec![ "a7a8b", "a7a8n", "a7a8q", "a7a8r" ]);
+
+        let mut board = game.load("k6K/8/8/8/8/8/p7/8 b - - 0 1");
+        assert_eq!(piece_moves(&mut board, PAWN as u8), vec![ "a2a1b", "a2a1n", "a2a1q", "a2a1r" ]);
+    }
+
+    #[test]
+    fn configured_promotions() {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+        let bounds = Bounds::new(8, 8);
+
+        // Knights promote to queens in the last two ranks, which is only forced on the last.
+        game.promotion = PromotionRules::new(bounds, 2).promote(KNIGHT, vec![ QUEEN ], 1);
+        let mut board = game.load("k7/8/8/1N6/8/8/8/7K w - - 0 1");
+        let moves = piece_moves(&mut board, KNIGHT as u8);
+        assert!(moves.contains(&"b5c7".to_string()) && moves.contains(&"b5c7q".to_string()));
+        assert!(moves.contains(&"b5d4".to_string()) && !moves.contains(&"b5d4q".to_string()));
+
+        let mut board = game.load("k7/8/2N5/8/8/8/8/7K w - - 0 1");
+        let moves = piece_moves(&mut board, KNIGHT as u8);
+        assert!(moves.contains(&"c6d8q".to_string()) && !moves.contains(&"c6d8".to_string()));
+
+        // Moves leaving the zone can promote too.
+        game.promotion = PromotionRules::new(bounds, 2).promote(KNIGHT, vec![ QUEEN ], 0).from_zone();
+        let mut board = game.load("k7/2N5/8/8/8/8/8/7K w - - 0 1");
+        let moves = piece_moves(&mut board, KNIGHT as u8);
+        assert!(moves.contains(&"c7d5".to_string()) && moves.contains(&"c7d5q".to_string()));
+    }
+
+    #[test]
+    fn limited_promotions() {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+        let bounds = Bounds::new(8, 8);
+
+        // Like Grand Chess, pawns may only promote into pieces their team has lost.
+        game.promotion = PromotionRules::new(bounds, 1)
+            .promote(PAWN, vec![ KNIGHT, BISHOP, ROOK, QUEEN ], 1)
+            .limit(KNIGHT, 0)
+            .limit(BISHOP, 0)
+            .limit(ROOK, 2)
+            .limit(QUEEN, 1);
+
+        let mut board = game.load("8/P7/8/8/8/8/8/k2QK2R w - - 0 1");
+        assert_eq!(piece_moves(&mut board, PAWN as u8), vec![ "a7a8r" ]);
+
+        // With nothing to promote into, the pawn can't move onto the last rank at all.
+        let mut board = game.load("8/P7/8/8/8/8/8/kR1QK2R w - - 0 1");
+        assert!(piece_moves(&mut board, PAWN as u8).is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::bitboard::{BitBoard, BitInt};
+
+use super::{action::{make_chess_move, Action}, Board, GameState, Team};
+
+/// Which pieces of a game are royal, and how they decide legality and the end of the game.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Royalty {
+    /// Every royal piece must be kept out of attack, like the king in chess (or the knight in Knightmate).
+    /// A team without legal actions is checkmated if one of its royals is attacked, and stalemated otherwise.
+    AllSafe(Vec<usize>),
+    /// Royal pieces can be left under attack and captured, and a team loses once all of its royal pieces are gone.
+    LastStanding(Vec<usize>),
+    /// There are no royal pieces, and a team loses once it has no pieces of any of these types left.
+    /// With no types given, a team only loses once it has no pieces at all.
+    Extinction(Vec<usize>)
+}
+
+impl Royalty {
+    /// The royal pieces of `team`.
+    pub fn royals<T : BitInt, const N: usize>(&self, board: &Board<T, N>, team: Team) -> BitBoard<T> {
+        match self {
+            Royalty::AllSafe(royals) | Royalty::LastStanding(royals) => royals.iter()
+                .fold(BitBoard::default(), |pieces, &royal| pieces.or(board.state.pieces[royal]))
+                .and(board.state.team(team)),
+            Royalty::Extinction(_) => BitBoard::default()
+        }
+    }
+
+    /// Whether any royal piece of the team to move is attacked.
+    pub fn in_check<T : BitInt, const N: usize>(&self, board: &mut Board<T, N>) -> bool {
+        let royals = self.royals(board, board.state.moving_team);
+        royals.set() && board.attacked_by(royals, board.state.moving_team.next())
+    }
+
+    /// Whether the team that just moved (the team not to move) left its royals safe.
+    pub fn is_legal<T : BitInt, const N: usize>(&self, board: &mut Board<T, N>) -> bool {
+        match self {
+            Royalty::AllSafe(_) => {
+                let royals = self.royals(board, board.state.moving_team.next());
+                !board.attacks(royals)
+            }
+            Royalty::LastStanding(_) | Royalty::Extinction(_) => true
+        }
+    }
+
+    /// Whether the team to move has lost its royals, or gone extinct.
+    pub fn has_lost<T : BitInt, const N: usize>(&self, board: &Board<T, N>) -> bool {
+        let team = board.state.team_to_move();
+        match self {
+            Royalty::AllSafe(_) => false,
+            Royalty::LastStanding(_) => self.royals(board, board.state.moving_team).empty(),
+            Royalty::Extinction(types) if types.is_empty() => team.empty(),
+            Royalty::Extinction(types) => types.iter().any(|&piece| board.state.pieces[piece].and(team).empty())
+        }
+    }
+
+    pub fn game_state<T : BitInt, const N: usize>(&self, board: &mut Board<T, N>, actions: &[Action]) -> GameState {
+        let winner = board.state.moving_team.next();
+
+        if self.has_lost(board) {
+            GameState::Win(winner)
+        } else if !actions.is_empty() {
+            GameState::Ongoing
+        } else if matches!(self, Royalty::AllSafe(_)) && self.in_check(board) {
+            GameState::Win(winner)
+        } else {
+            GameState::Draw
+        }
+    }
+}
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    /// Whether any royal piece of the team to move is attacked.
+    pub fn in_check(&mut self) -> bool {
+        self.game.royalty.in_check(self)
+    }
+
+    /// The enemy pieces attacking royal pieces of the team to move.
+    pub fn checkers(&mut self) -> BitBoard<T> {
+        let team = self.state.moving_team;
+        let occupancy = self.state.white.or(self.state.black);
+
+        self.game.royalty.royals(self, team)
+            .iter()
+            .fold(BitBoard::default(), |checkers, royal| {
+                checkers.or(self.attackers_to(royal as u16, team.next(), occupancy))
+            })
+    }
+
+    /// Whether `action` would attack a royal piece of the other team.
+    ///
+    /// Plain actions (with an `info` of 0) are only moved on the board state, without playing them through the game's rules,
+    /// so they're assumed to move like `make_chess_move`. Other actions, like castling, promotions or drops, are played and restored.
+    pub fn gives_check(&mut self, action: Action) -> bool {
+        let team = self.state.moving_team;
+        if self.game.royalty.royals(self, team.next()).empty() {
+            return false;
+        }
+
+        if action.info != 0 || action.is_drop() {
+            let state = self.play(action);
+            let check = self.in_check();
+            self.restore(state);
+            return check;
+        }
+
+        let state = self.state.clone();
+        make_chess_move(&mut self.state, action);
+
+        let royals = self.game.royalty.royals(self, team.next());
+        let check = royals.set() && self.attacked_by(royals, team);
+
+        self.state = state;
+        check
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{Chess, SliderMoves, KING, KNIGHT, ROOK}, game::{Board, GameState, GameTemplate, Team}};
+
+    use super::Royalty;
+
+    fn state(board: &mut Board<u64, 6>) -> GameState {
+        let legals = board.legals();
+        board.game_state(&legals)
+    }
+
+    #[test]
+    fn royal_pieces_must_be_safe() {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+
+        // Like Knightmate, a royal knight must escape the rook's attack, so the (unroyal) king can't move.
+        game.royalty = Royalty::AllSafe(vec![ KNIGHT ]);
+        let mut board = game.load("4k3/8/8/8/8/8/8/r3N2K w - - 0 1");
+        assert!(game.royalty.in_check(&mut board));
+        let legals = board.legals();
+        assert!(!legals.is_empty() && legals.iter().all(|action| action.piece == KNIGHT as u8));
+
+        // With two royal kings, the attacked one has to move.
+        game.royalty = Royalty::AllSafe(vec![ KING ]);
+        let mut board = game.load("4k3/8/8/8/8/8/5K2/r2K4 w - - 0 1");
+        let legals = board.legals();
+        assert!(!legals.is_empty() && legals.iter().all(|action| action.from == 3));
+    }
+
+    #[test]
+    fn last_royal_standing() {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+        game.royalty = Royalty::LastStanding(vec![ KING ]);
+
+        // Either king can be left under attack.
+        let mut board = game.load("4k3/8/8/8/8/8/5K2/r2K4 w - - 0 1");
+        assert!(board.legals().iter().any(|action| action.from == 13));
+
+        let mut board = game.load("4k3/8/8/8/8/8/8/7R w - - 0 1");
+        assert!(matches!(state(&mut board), GameState::Win(Team::Black)));
+    }
+
+    #[test]
+    fn extinction() {
+        let mut game = Chess::<SliderMoves>::create::<u64, 6>();
+
+        game.royalty = Royalty::Extinction(vec![]);
+        let mut board = game.load("4k3/8/8/8/8/8/8/8 w - - 0 1");
+        assert!(matches!(state(&mut board), GameState::Win(Team::Black)));
+
+        let mut board = game.load("4k3/8/8/8/8/8/8/r3K3 w - - 0 1");
+        assert!(matches!(state(&mut board), GameState::Ongoing));
+
+        // Losing every rook loses the game, even with the king left.
+        game.royalty = Royalty::Extinction(vec![ ROOK ]);
+        let mut board = game.load("r3k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert!(matches!(state(&mut board), GameState::Win(Team::Black)));
+    }
+}
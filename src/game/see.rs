@@ -0,0 +1,206 @@
+use crate::bitboard::{BitBoard, BitInt};
+
+use super::{action::Action, promotion::is_promotion, royalty::Royalty, Board};
+
+impl<'a, T : BitInt, const N: usize> Board<'a, T, N> {
+    /// How much `piece` is worth under `Game::values`.
+    #[inline(always)]
+    pub fn piece_value(&self, piece: usize) -> i32 {
+        self.game.values.get(piece).copied().unwrap_or(0)
+    }
+
+    /// Static exchange evaluation; whether `action` wins at least `threshold` once the captures on `action.to` are played out,
+    /// with both teams capturing with their least valuable attacker, and free to stop whenever continuing would lose them more.
+    ///
+    /// Attackers are found with `attackers_to`, taking each capturing piece out of the occupancy,
+    /// so x-ray attackers behind them are revealed for any slider on the game. Pins are ignored.
+    /// The moving piece's rules say which square it captures on (see `PieceRules::captured_square`),
+    /// so en passant takes the pawn behind `action.to`, and castling wins nothing.
+    pub fn see(&mut self, action: Action, threshold: i32) -> bool {
+        let Some(captured) = self.game.pieces[action.piece as usize].rules.captured_square(self, action) else {
+            return threshold <= 0;
+        };
+
+        let promotion = is_promotion(self, action);
+        let piece = if promotion { (action.info - 2) as usize } else { action.piece as usize };
+
+        let mut swap = self.state.piece_at(captured).map_or(0, |victim| self.piece_value(victim)) - threshold;
+        if promotion {
+            swap += self.piece_value(piece) - self.piece_value(action.piece as usize);
+        }
+
+        if swap < 0 {
+            return false;
+        }
+
+        // Even if the piece is captured for nothing, the exchange is still good enough.
+        swap = self.piece_value(piece) - swap;
+        if swap <= 0 {
+            return true;
+        }
+
+        let mut occupancy = self.state.white.or(self.state.black)
+            .and_not(BitBoard::index(action.from))
+            .and_not(BitBoard::index(action.to))
+            .and_not(BitBoard::index(captured));
+        let safe_royals = matches!(self.game.royalty, Royalty::AllSafe(_));
+
+        let mut team = self.state.moving_team;
+        let mut result = true;
+
+        loop {
+            team = team.next();
+
+            let attackers = self.attackers_to(action.to, team, occupancy);
+            if attackers.empty() {
+                break;
+            }
+
+            result = !result;
+
+            let (attacker, value) = self.least_valuable(attackers);
+            if safe_royals && self.game.royalty.royals(self, team).and(attacker).set() {
+                // A royal piece can only capture if it can't be captured back.
+                let defended = self.attackers_to(action.to, team.next(), occupancy).set();
+                return if defended { !result } else { result };
+            }
+
+            swap = value - swap;
+            if swap < result as i32 {
+                break;
+            }
+
+            occupancy = occupancy.and_not(attacker);
+        }
+
+        result
+    }
+
+    /// The least valuable piece among `attackers`, and its value.
+    fn least_valuable(&self, attackers: BitBoard<T>) -> (BitBoard<T>, i32) {
+        let mut least = (BitBoard::default(), i32::MAX);
+
+        for piece in 0..self.game.pieces.len() {
+            let value = self.piece_value(piece);
+            let pieces = self.state.pieces[piece].and(attackers);
+
+            if value < least.1 && pieces.set() {
+                least = (BitBoard::index(pieces.bitscan_forward() as u16), value);
+            }
+        }
+
+        least
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bitboard::BitBoard, chess::{pieces::{compound::mover_attackers_to, leapers::{knight::KnightMoves, leaper::Leaper}}, Chess, MagicMoves, SliderMoves, KNIGHT}, game::{action::Action, piece::{Piece, PieceRules}, Board, Game, GameTemplate, Team}};
+
+    /// A knight whose actions are all marked as special, though they capture like any other knight's.
+    struct MarkedKnight;
+
+    impl PieceRules<u64, 6> for MarkedKnight {
+        fn process(&mut self, game: &mut Game<u64, 6>, piece_index: usize) {
+            Leaper(KnightMoves).process(game, piece_index);
+        }
+
+        fn add_actions(&self, board: &mut Board<u64, 6>, actions: &mut Vec<Action>, piece_index: usize) {
+            let start = actions.len();
+            Leaper(KnightMoves).add_actions(board, actions, piece_index);
+            for action in &mut actions[start..] {
+                *action = action.with_info(1);
+            }
+        }
+
+        fn attacks(&self, board: &mut Board<u64, 6>, piece_index: usize, mask: BitBoard<u64>) -> bool {
+            Leaper(KnightMoves).attacks(board, piece_index, mask)
+        }
+
+        fn attackers_to(&self, board: &mut Board<u64, 6>, piece_index: usize, square: u16, team: Team, occupancy: BitBoard<u64>) -> BitBoard<u64> {
+            mover_attackers_to(&Leaper(KnightMoves), board, piece_index, piece_index, square, team, occupancy)
+        }
+    }
+
+    #[test]
+    fn chess_see() {
+        for chess in [ Chess::<MagicMoves>::create::<u64, 6>(), Chess::<SliderMoves>::create::<u64, 6>() ] {
+            // The pawn on e5 is free to take.
+            let mut board = chess.load("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1");
+            let action = board.find_action("e1e5");
+            assert!(board.see(action, 100));
+            assert!(!board.see(action, 101));
+
+            // Knight for pawn, then the exchanges stop: white wins a pawn but loses a knight.
+            let mut board = chess.load("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1");
+            let action = board.find_action("d3e5");
+            assert!(board.see(action, -220));
+            assert!(!board.see(action, -219));
+
+            // The d1 rook x-rays through the d2 rook, so white can take back on d6 after cxd6.
+            let mut board = chess.load("k7/2p5/3p4/8/8/8/3R4/K2R4 w - - 0 1");
+            let action = board.find_action("d2d6");
+            assert!(board.see(action, -300));
+            assert!(!board.see(action, -299));
+
+            // The king can only take the rook back when the queen isn't defending d4.
+            let mut board = chess.load("8/8/8/2k5/3p4/8/3R4/K2Q4 w - - 0 1");
+            let action = board.find_action("d2d4");
+            assert!(board.see(action, 100));
+
+            let mut board = chess.load("8/8/8/2k5/3p4/8/3R4/K7 w - - 0 1");
+            let action = board.find_action("d2d4");
+            assert!(board.see(action, -400));
+            assert!(!board.see(action, 0));
+
+            // Promoting counts the new queen, less the pawn it was.
+            let mut board = chess.load("7k/P7/8/8/8/8/8/K7 w - - 0 1");
+            let action = board.find_action("a7a8q");
+            assert!(board.see(action, 800));
+            assert!(!board.see(action, 801));
+
+            // En passant wins the d5 pawn, unless the d8 rook takes back.
+            let mut board = chess.load("7k/8/8/3pP3/8/8/8/K7 w - d6 0 1");
+            let action = board.find_action("e5d6");
+            assert!(board.see(action, 100));
+            assert!(!board.see(action, 101));
+
+            let mut board = chess.load("3r3k/8/8/3pP3/8/8/8/K7 w - d6 0 1");
+            let action = board.find_action("e5d6");
+            assert!(board.see(action, 0));
+            assert!(!board.see(action, 1));
+
+            // Taking the d5 pawn lets the d1 rook defend d6, so the d8 rook can't take back.
+            let mut board = chess.load("3r3k/8/8/3pP3/8/8/8/K2R4 w - d6 0 1");
+            let action = board.find_action("e5d6");
+            assert!(board.see(action, 100));
+            assert!(!board.see(action, 101));
+
+            // Castling still wins nothing.
+            let mut board = chess.load("4k3/8/8/8/8/8/8/4K2R w K - 0 1");
+            let action = board.find_action("e1g1");
+            assert!(board.see(action, 0));
+            assert!(!board.see(action, 1));
+        }
+    }
+
+    #[test]
+    fn special_actions_capture_where_their_piece_says() {
+        let mut chess = Chess::<SliderMoves>::create::<u64, 6>();
+        chess.pieces[KNIGHT] = Piece::new("knight", 'n', MarkedKnight);
+        chess.process_pieces();
+
+        // The knight takes the pawn on e4, though there's nothing behind it to take en passant.
+        let mut board = chess.load("4k3/8/8/8/4p3/8/3N4/K7 w - - 0 1");
+        let action = board.find_action("d2e4");
+        assert_eq!(action.info, 1);
+        assert!(board.see(action, 100));
+        assert!(!board.see(action, 101));
+
+        // The knight takes the rook on e5, not the pawn behind it.
+        let mut board = chess.load("4k3/8/8/4r3/4p3/3N4/8/K7 w - - 0 1");
+        let action = board.find_action("d3e5");
+        assert!(board.see(action, 500));
+        assert!(!board.see(action, 501));
+    }
+}
@@ -31,40 +31,230 @@ pub fn parse_suite(positions: &str) -> Vec<Position> {
     out
 }
 
-pub fn test_suite<'a, T : BitInt, const N: usize>(positions: &str, game: &Game<T, N>) {
+/// Limits for a suite run. Depths deeper than `max_depth` are skipped, and once `time_limit` (in milliseconds)
+/// has elapsed no further perfts are started. With `threads` set, each perft is run with `perft_parallel`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SuiteOptions {
+    pub max_depth: Option<usize>,
+    pub time_limit: Option<u128>,
+    pub threads: Option<usize>
+}
+
+#[derive(Debug, Clone)]
+pub struct DepthResult {
+    pub depth: usize,
+    pub expected: usize,
+    pub found: usize,
+    /// Time spent on this perft, in milliseconds.
+    pub time: u128,
+    pub nps: usize
+}
+
+impl DepthResult {
+    pub fn passed(&self) -> bool {
+        self.found == self.expected
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionResult {
+    pub index: usize,
+    pub pos: String,
+    pub depths: Vec<DepthResult>
+}
+
+impl PositionResult {
+    pub fn passed(&self) -> bool {
+        self.depths.iter().all(|depth| depth.passed())
+    }
+
+    pub fn first_failure(&self) -> Option<&DepthResult> {
+        self.depths.iter().find(|depth| !depth.passed())
+    }
+}
+
+/// The divide of the first failing position, at the shallowest depth that failed.
+#[derive(Debug, Clone)]
+pub struct SuiteDivide {
+    pub pos: String,
+    pub depth: usize,
+    pub lines: Vec<(String, usize)>
+}
+
+#[derive(Debug, Clone)]
+pub struct SuiteResult {
+    pub positions: Vec<PositionResult>,
+    pub total_nodes: usize,
+    pub time: u128,
+    pub nps: usize,
+    /// Whether the time limit cut the run short.
+    pub timed_out: bool,
+    pub divide: Option<SuiteDivide>
+}
+
+impl SuiteResult {
+    pub fn passed(&self) -> bool {
+        self.positions.iter().all(|position| position.passed())
+    }
+
+    pub fn failures(&self) -> Vec<(&PositionResult, &DepthResult)> {
+        self.positions.iter()
+            .flat_map(|position| position.depths.iter()
+                .filter(|depth| !depth.passed())
+                .map(move |depth| (position, depth)))
+            .collect()
+    }
+}
+
+fn nps(nodes: usize, time: u128) -> usize {
+    let time = time.max(1);
+    ((nodes as u128) * 1000 / time) as usize
+}
+
+pub fn run_suite<T : BitInt, const N: usize>(positions: &str, game: &Game<T, N>, options: SuiteOptions) -> SuiteResult {
+    run_suite_with(positions, game, options, |_, _, _| {})
+}
+
+/// Same as `run_suite`, but calls `on_result` with each position's index, the position, and its result at a depth
+/// as soon as that perft finishes.
+pub fn run_suite_with<T : BitInt, const N: usize>(
+    positions: &str,
+    game: &Game<T, N>,
+    options: SuiteOptions,
+    mut on_result: impl FnMut(usize, &Position, &DepthResult)
+) -> SuiteResult {
     let positions = parse_suite(positions);
+    let mut results = Vec::with_capacity(positions.len());
     let mut total_nodes = 0;
+    let mut timed_out = false;
 
     let full_start = current_time_millis();
 
-    for (pos_ind, position) in positions.iter().enumerate() {
+    'positions: for (pos_ind, position) in positions.iter().enumerate() {
         let mut board = game.load(&position.pos);
+        let mut depths = vec![];
 
         for (index, nodes) in position.nodes.iter().enumerate() {
             let depth = index + 1;
 
-            let start = current_time_millis();
-            let found_nodes = board.perft(depth);
-            let end = current_time_millis();
+            if options.max_depth.is_some_and(|max_depth| depth > max_depth) {
+                break;
+            }
 
-            total_nodes += found_nodes;
+            if options.time_limit.is_some_and(|limit| current_time_millis() - full_start >= limit) {
+                timed_out = true;
+                results.push(PositionResult { index: pos_ind, pos: position.pos.clone(), depths });
+                break 'positions;
+            }
 
-            let mut time = (end - start) as usize;
-            if time == 0 { time = 1 };
+            let start = current_time_millis();
+            let found = match options.threads {
+                Some(threads) => board.perft_parallel(depth, threads),
+                None => board.perft(depth)
+            };
+            let time = current_time_millis() - start;
 
-            let nps = found_nodes / time * 1000;
+            total_nodes += found;
 
-            println!("[#{}] {} ({} depth) - {} found - {} expected ({} nps)", pos_ind + 1, position.pos, depth, found_nodes, nodes, nps);
-            
-            assert_eq!(found_nodes, *nodes as usize, "{} found - {} expected", found_nodes, nodes);
+            let result = DepthResult {
+                depth,
+                expected: *nodes as usize,
+                found,
+                time,
+                nps: nps(found, time)
+            };
+            on_result(pos_ind, position, &result);
+            depths.push(result);
         }
+
+        results.push(PositionResult { index: pos_ind, pos: position.pos.clone(), depths });
     }
 
-    let full_end = current_time_millis();
-    let time = (full_end - full_start) as usize;
-    let nps = total_nodes / time * 1000;
+    let time = current_time_millis() - full_start;
 
-    println!("Total nodes: {} ({} nps)", total_nodes, nps);
+    let divide = results.iter()
+        .find_map(|position| position.first_failure().map(|depth| (position, depth.depth)))
+        .map(|(position, depth)| SuiteDivide {
+            pos: position.pos.clone(),
+            depth,
+            lines: game.load(&position.pos).divide_uci(depth)
+        });
 
+    SuiteResult {
+        positions: results,
+        total_nodes,
+        time,
+        nps: nps(total_nodes, time),
+        timed_out,
+        divide
+    }
 }
 
+/// Runs the suite, printing each result as it finishes, and panics at the first one that doesn't match (after printing its divide).
+pub fn test_suite<T : BitInt, const N: usize>(positions: &str, game: &Game<T, N>) {
+    let result = run_suite_with(positions, game, SuiteOptions::default(), |index, position, depth| {
+        println!(
+            "[#{}] {} ({} depth) - {} found - {} expected ({} nps)",
+            index + 1, position.pos, depth.depth, depth.found, depth.expected, depth.nps
+        );
+
+        if !depth.passed() {
+            println!("Divide of {} ({} depth):", position.pos, depth.depth);
+            for (action, nodes) in game.load(&position.pos).divide_uci(depth.depth) {
+                println!("{} - {}", action, nodes);
+            }
+
+            panic!("[#{}] {} ({} depth) - {} found - {} expected", index + 1, position.pos, depth.depth, depth.found, depth.expected);
+        }
+    });
+
+    println!("Total nodes: {} ({} nps)", result.total_nodes, result.nps);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{chess::{Chess, MagicMoves}, game::GameTemplate};
+
+    use super::{run_suite, run_suite_with, SuiteOptions};
+
+    const BROKEN_SUITE: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1;20;401;8902
+4k3/8/8/8/8/8/8/4K2R w K - 0 1;15;66;1198";
+
+    #[test]
+    fn suite_reports_all_failures() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let result = run_suite(BROKEN_SUITE, &chess, SuiteOptions::default());
+
+        assert!(!result.passed());
+        assert_eq!(result.failures().len(), 2);
+
+        let divide = result.divide.expect("First failure should have a divide");
+        assert_eq!(divide.depth, 2);
+        assert_eq!(divide.lines.len(), 20);
+        assert_eq!(divide.lines.iter().map(|(_, nodes)| nodes).sum::<usize>(), 400);
+    }
+
+    #[test]
+    fn suite_streams_results() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+
+        // Results come in as each perft finishes, in the order they're run.
+        let mut streamed = vec![];
+        let result = run_suite_with(BROKEN_SUITE, &chess, SuiteOptions::default(), |index, _, depth| {
+            streamed.push((index, depth.depth, depth.passed()));
+        });
+
+        assert_eq!(streamed, vec![ (0, 1, true), (0, 2, false), (0, 3, true), (1, 1, true), (1, 2, true), (1, 3, false) ]);
+        assert_eq!(result.positions.iter().map(|position| position.depths.len()).sum::<usize>(), streamed.len());
+    }
+
+    #[test]
+    fn suite_depth_limit() {
+        let chess = Chess::<MagicMoves>::create::<u64, 6>();
+        let result = run_suite(BROKEN_SUITE, &chess, SuiteOptions { max_depth: Some(1), ..Default::default() });
+
+        assert!(result.passed());
+        assert!(result.divide.is_none());
+        assert!(result.positions.iter().all(|position| position.depths.len() == 1));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod bitboard;
 pub mod game;
 pub mod chess;
+pub mod shogi;
 pub mod uci;
\ No newline at end of file
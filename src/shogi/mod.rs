@@ -0,0 +1,519 @@
+use crate::{bitboard::{BitBoard, BitInt, Bounds}, chess::pieces::{compound::Compound, leapers::{general::{GoldMoves, SilverMoves}, king::KingMoves, leaper::Leaper, shogi::{ShogiKnightMoves, ShogiPawnMoves}}, sliders::{bishop::BishopMoves, lance::LanceMoves, rook::RookMoves, slider::Slider}}, game::{action::{Action, ActionRecord}, drops::DropRules, piece::Piece, promotion::{is_promotion, PromotionRules}, royalty::Royalty, zobrist::ZobristTable, Board, BoardState, Game, GameRules, GameState, GameTemplate, LookupSlots, Team}};
+
+pub const SHOGI_SUITE: &str = "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1;30;900;25470;719731
+l6nl/5+P1gk/2np1S3/p1p4Pp/3P2Sp1/1PPb2P1P/P5GS1/R8/LN4bKL w RGgsn5p 1;207;28684;4809015
+R8/2K1S1SSk/4B4/9/9/9/9/9/1L1L1L3 b RBGSNLP3g3n17p 1;593;105677";
+
+// The indexes `Shogi` registers its pieces at, with each promoted piece after the unpromoted ones.
+
+pub const PAWN: usize = 0;
+pub const LANCE: usize = 1;
+pub const KNIGHT: usize = 2;
+pub const SILVER: usize = 3;
+pub const GOLD: usize = 4;
+pub const BISHOP: usize = 5;
+pub const ROOK: usize = 6;
+pub const KING: usize = 7;
+pub const TOKIN: usize = 8;
+pub const PROMOTED_LANCE: usize = 9;
+pub const PROMOTED_KNIGHT: usize = 10;
+pub const PROMOTED_SILVER: usize = 11;
+pub const HORSE: usize = 12;
+pub const DRAGON: usize = 13;
+
+/// The order pieces are written in the hands of an SFEN.
+const HAND_ORDER: [usize; 7] = [ ROOK, BISHOP, GOLD, SILVER, KNIGHT, LANCE, PAWN ];
+
+pub fn pieces<T : BitInt, const N: usize>() -> Vec<Piece<T, N>> {
+    vec![
+        Piece::new("pawn", 'p', Leaper(ShogiPawnMoves)),
+        Piece::new("lance", 'l', Slider(LanceMoves)),
+        Piece::new("knight", 'n', Leaper(ShogiKnightMoves)),
+        Piece::new("silver", 's', Leaper(SilverMoves)),
+        Piece::new("gold", 'g', Leaper(GoldMoves)),
+        Piece::new("bishop", 'b', Slider(BishopMoves)),
+        Piece::new("rook", 'r', Slider(RookMoves)),
+        Piece::new("king", 'k', Leaper(KingMoves)),
+        // Promoted pieces share the symbol of the piece they promoted from, and are written with a `+` before it.
+        Piece::new("tokin", 'p', Leaper(GoldMoves)),
+        Piece::new("promoted lance", 'l', Leaper(GoldMoves)),
+        Piece::new("promoted knight", 'n', Leaper(GoldMoves)),
+        Piece::new("promoted silver", 's', Leaper(GoldMoves)),
+        Piece::new("horse", 'b', Compound::new(Slider(BishopMoves), Leaper(KingMoves))),
+        Piece::new("dragon", 'r', Compound::new(Slider(RookMoves), Leaper(KingMoves)))
+    ]
+}
+
+/// Converts a square index into USI notation, where files count down from 9 on the left, and ranks go from `a` at the top.
+pub fn index_to_usi(index: u16, bounds: Bounds) -> String {
+    let file = bounds.cols - index % bounds.cols;
+    let rank = (b'a' + (bounds.rows - 1 - index / bounds.cols) as u8) as char;
+
+    format!("{}{}", file, rank)
+}
+
+/// Whether two states hold the same position, as far as sennichite is concerned.
+fn same_position<T : BitInt, const N: usize>(first: &BoardState<T, N>, second: &BoardState<T, N>) -> bool {
+    first.moving_team == second.moving_team
+        && first.white == second.white
+        && first.black == second.black
+        && first.pieces == second.pieces
+        && first.pockets == second.pockets
+}
+
+/// Sennichite; whether the current position has appeared for the fourth time since the board was loaded,
+/// looking back through `Board::positions`.
+///
+/// A repetition is a draw, unless one team checked with every one of its moves since the position first repeated, in which case it loses.
+pub fn sennichite<T : BitInt, const N: usize>(board: &mut Board<T, N>) -> Option<GameState> {
+    let repeats: Vec<usize> = board.positions.iter()
+        .enumerate()
+        .filter(|(_, state)| same_position(state, &board.state))
+        .map(|(index, _)| index)
+        .collect();
+
+    if repeats.len() < 3 {
+        return None;
+    }
+
+    // Indexed by team; whether the team was in check every time it was to move.
+    let mut checked = [ true, true ];
+    let current = board.state.clone();
+    let states = board.positions[repeats[repeats.len() - 3]..].to_vec();
+
+    for state in states {
+        let team = state.moving_team;
+        board.state = state;
+        if !board.in_check() {
+            checked[team.index()] = false;
+        }
+    }
+
+    board.state = current;
+    if !board.in_check() {
+        checked[board.state.moving_team.index()] = false;
+    }
+
+    Some(match checked {
+        [ true, false ] => GameState::Win(Team::White),
+        [ false, true ] => GameState::Win(Team::Black),
+        _ => GameState::Draw
+    })
+}
+
+pub struct ShogiProcessor;
+
+impl<T : BitInt, const N: usize> GameRules<T, N> for ShogiProcessor {
+    fn actions(&self, board: &mut Board<T, N>) -> Vec<Action> {
+        board.piece_actions()
+    }
+
+    fn attacks(&self, board: &mut Board<T, N>, mask: BitBoard<T>) -> bool {
+        board.piece_attacks(mask)
+    }
+
+    fn attackers_to(&self, board: &mut Board<T, N>, square: u16, team: Team, occupancy: BitBoard<T>) -> BitBoard<T> {
+        board.piece_attackers_to(square, team, occupancy)
+    }
+
+    /// Keeps every position in `Board::positions`, for sennichite.
+    fn play(&self, board: &mut Board<T, N>, act: Action) {
+        board.positions.push(board.state.clone());
+        board.make_piece_move(act);
+    }
+
+    /// Besides keeping the king safe, a pawn can't be dropped to give checkmate (uchifuzume).
+    fn is_legal(&self, board: &mut Board<T, N>) -> bool {
+        if !board.game.royalty.is_legal(board) {
+            return false;
+        }
+
+        match board.history.last() {
+            Some(&ActionRecord::Action(last)) if last.is_drop() && last.piece as usize == PAWN => {
+                !board.in_check() || !board.legals().is_empty()
+            }
+            _ => true
+        }
+    }
+
+    fn display_action(&self, board: &mut Board<T, N>, act: Action) -> Vec<String> {
+        let bounds = board.game.bounds;
+
+        if act.is_drop() {
+            let piece_map = board.game.piece_map();
+            return vec![
+                format!("{}*{}", piece_map[act.piece as usize].to_ascii_uppercase(), index_to_usi(act.to, bounds))
+            ];
+        }
+
+        let promotion = if is_promotion(board, act) { "+" } else { "" };
+        vec![
+            format!("{}{}{}", index_to_usi(act.from, bounds), index_to_usi(act.to, bounds), promotion)
+        ]
+    }
+
+    /// Loads an SFEN, like `lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1`.
+    /// Sente (`b`, in uppercase) is `Team::White`, moving up the board.
+    fn load(&self, board: &mut Board<T, N>, pos: &str) {
+        let parts: Vec<&str> = pos.split(" ").collect();
+        let piece_map = board.game.piece_map();
+
+        // Piece Placement
+        for (y, row) in parts[0].split("/").enumerate() {
+            let y = y as u16;
+            let mut x: u16 = 0;
+            let mut promoted = false;
+
+            for char in row.chars() {
+                if let Some(skip) = char.to_digit(10) {
+                    x += skip as u16;
+                    continue;
+                }
+
+                if char == '+' {
+                    promoted = true;
+                    continue;
+                }
+
+                if let Some(mut piece) = piece_map.iter().position(|&n| n == char.to_ascii_lowercase()) {
+                    let square = BitBoard::coords(x, y, board.game.bounds);
+
+                    if promoted {
+                        piece = board.game.promotion.promotion(piece).map_or(piece, |promotion| promotion.targets[0]);
+                        board.state.promoted = board.state.promoted.or(square);
+                    }
+
+                    board.state.pieces[piece] = board.state.pieces[piece].or(square);
+
+                    if char.is_lowercase() {
+                        board.state.black = board.state.black.or(square);
+                    } else {
+                        board.state.white = board.state.white.or(square);
+                    }
+                }
+
+                promoted = false;
+                x += 1;
+            }
+        }
+
+        // Team to Move
+        board.state.moving_team = if parts[1] == "b" { Team::White } else { Team::Black };
+
+        // Move Count, starting from 1
+        let move_count = parts.get(3).and_then(|count| count.parse::<usize>().ok()).unwrap_or(1);
+        board.earlier_moves = move_count.saturating_sub(1);
+
+        // Pieces in Hand, with counts before pieces held more than once (like `2P3pS`)
+        let mut count = 0;
+        for char in parts[2].chars() {
+            if let Some(digit) = char.to_digit(10) {
+                count = count * 10 + digit as u8;
+                continue;
+            }
+
+            if let Some(piece) = piece_map.iter().position(|&n| n == char.to_ascii_lowercase()) {
+                let team = if char.is_lowercase() { Team::Black } else { Team::White };
+                board.state.pockets[team.index()][piece] += count.max(1);
+            }
+
+            count = 0;
+        }
+    }
+
+    fn save(&self, board: &mut Board<T, N>) -> String {
+        let piece_map = board.game.piece_map();
+        let bounds = board.game.bounds;
+        let mut rows = vec![];
+
+        for row in (0..bounds.rows).rev() {
+            let mut row_str = String::new();
+            let mut empty_count = 0;
+
+            for col in 0..bounds.cols {
+                let index = row * bounds.cols + col;
+                let piece = match board.state.piece_at(index) {
+                    Some(piece) => piece,
+                    None => {
+                        empty_count += 1;
+                        continue;
+                    }
+                };
+
+                if empty_count > 0 {
+                    row_str.push_str(&empty_count.to_string());
+                    empty_count = 0;
+                }
+
+                if board.game.promotion.demotion(piece) != piece {
+                    row_str.push('+');
+                }
+
+                row_str.push(match board.state.white.and(BitBoard::index(index)).set() {
+                    true => piece_map[piece].to_ascii_uppercase(),
+                    false => piece_map[piece]
+                });
+            }
+
+            if empty_count > 0 {
+                row_str.push_str(&empty_count.to_string());
+            }
+
+            rows.push(row_str);
+        }
+
+        let moving_team = match board.state.moving_team {
+            Team::White => "b",
+            Team::Black => "w"
+        };
+
+        let mut hands = String::new();
+        for team in [ Team::White, Team::Black ] {
+            for piece in HAND_ORDER {
+                let count = board.state.pockets[team.index()][piece];
+                if count > 1 {
+                    hands.push_str(&count.to_string());
+                }
+
+                if count > 0 {
+                    hands.push(match team {
+                        Team::White => piece_map[piece].to_ascii_uppercase(),
+                        Team::Black => piece_map[piece]
+                    });
+                }
+            }
+        }
+
+        if hands.is_empty() {
+            hands.push('-');
+        }
+
+        let move_count = board.earlier_moves + board.history.len() + 1;
+
+        format!("{} {} {} {}", rows.join("/"), moving_team, hands, move_count)
+    }
+
+    /// A team without legal actions loses, whether or not it's in check,
+    /// and the fourth repetition of a position ends the game by sennichite.
+    fn game_state(&self, board: &mut Board<T, N>, actions: &[Action]) -> GameState {
+        if actions.is_empty() {
+            GameState::Win(board.state.moving_team.next())
+        } else {
+            sennichite(board).unwrap_or(GameState::Ongoing)
+        }
+    }
+
+    /// Pieces can't stand where they would have had to promote, like pawns and lances on the last rank,
+    /// and knights on the last two.
+    fn unplaceable(&self, game: &Game<T, N>, piece: usize, team: Team) -> BitBoard<T> {
+        game.promotion.promotion(piece)
+            .map_or(BitBoard::default(), |promotion| promotion.forced[team.index()])
+    }
+
+    fn gen_zobrist(&self, board: &mut Board<T, N>, seed: u64) -> ZobristTable {
+        let pieces = N;
+        let squares = (board.game.bounds.rows * board.game.bounds.cols) as usize;
+        let teams = 2;
+
+        let piece_features = pieces * squares * teams;
+        let team_to_move_features = teams;
+        let hand_features = teams * pieces * (squares + 1);
+
+        ZobristTable::generate(piece_features + team_to_move_features + hand_features, seed)
+    }
+
+    fn hash(&self, board: &mut Board<T, N>, table: &ZobristTable) -> u64 {
+        let mut attrs = Vec::with_capacity(50);
+
+        let pieces = N;
+        let squares = (board.game.bounds.rows * board.game.bounds.cols) as usize;
+
+        let mut features = 0;
+
+        for team in [Team::White, Team::Black] {
+            let team_index = team.index();
+            for piece in 0..N {
+                let piece_team_board = board.state.pieces[piece].and(board.state.team(team));
+                for square in piece_team_board.iter() {
+                    attrs.push(
+                        (square as usize) + (piece * squares) + (team_index * pieces * squares)
+                    );
+                }
+            }
+        }
+
+        features += squares * pieces * 2;
+
+        attrs.push(board.state.moving_team.index() + features);
+
+        features += 2;
+
+        for team in [Team::White, Team::Black] {
+            for (piece, &count) in board.state.pockets[team.index()].iter().enumerate() {
+                if count > 0 {
+                    let count = (count as usize).min(squares);
+                    attrs.push(count + ((team.index() * pieces) + piece) * (squares + 1) + features);
+                }
+            }
+        }
+
+        table.compute(&attrs)
+    }
+}
+
+/// Shogi, on a 9x9 board. The board needs at least 81 bits (like `u128`), and 14 piece types:
+///
+/// ```rs
+/// let shogi = Shogi::create::<u128, 14>();
+/// ```
+pub struct Shogi;
+
+impl GameTemplate for Shogi {
+    fn create<T : BitInt, const N: usize>() -> Game<T, N> {
+        let bounds = Bounds::new(9, 9);
+        let mut game = Game {
+            rules: Box::new(ShogiProcessor),
+            pieces: pieces(),
+            bounds,
+            default_pos: "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1".to_string(),
+            // Pieces can promote moving into, within or out of the last three ranks,
+            // and have to once they'd otherwise have no moves left.
+            promotion: PromotionRules::new(bounds, 3)
+                .promote(PAWN, vec![ TOKIN ], 1)
+                .promote(LANCE, vec![ PROMOTED_LANCE ], 1)
+                .promote(KNIGHT, vec![ PROMOTED_KNIGHT ], 2)
+                .promote(SILVER, vec![ PROMOTED_SILVER ], 0)
+                .promote(BISHOP, vec![ HORSE ], 0)
+                .promote(ROOK, vec![ DRAGON ], 0)
+                .from_zone(),
+            drops: DropRules::new(bounds)
+                .drop(PAWN, 1)
+                .one_per_file(PAWN)
+                .drop(LANCE, 1)
+                .drop(KNIGHT, 2)
+                .drop(SILVER, 0)
+                .drop(GOLD, 0)
+                .drop(BISHOP, 0)
+                .drop(ROOK, 0),
+            royalty: Royalty::AllSafe(vec![ KING ]),
+            castling: None,
+            values: vec![ 100, 300, 350, 500, 550, 800, 1000, 20000, 550, 550, 550, 550, 1100, 1300 ],
+            lookup: LookupSlots::new(),
+            edges: vec![
+                BitBoard::edges(bounds, 1),
+                BitBoard::edges(bounds, 2)
+            ],
+            magics: LookupSlots::new(),
+            black_lookup: LookupSlots::indexes(),
+            reach: vec![]
+        };
+
+        game.process_pieces();
+
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Board, GameState, GameTemplate, Team};
+
+    use super::{Shogi, PAWN, SHOGI_SUITE};
+
+    fn moves(board: &mut Board<u128, 14>, filter: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut moves: Vec<String> = board.legals().into_iter()
+            .map(|action| board.display_uci_action(action))
+            .filter(|action| filter(action))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn shogi_suite() {
+        let shogi = Shogi::create::<u128, 14>();
+        crate::game::suite::test_suite(SHOGI_SUITE, &shogi);
+    }
+
+    #[test]
+    fn sfen_round_trip() {
+        let shogi = Shogi::create::<u128, 14>();
+
+        for sfen in [
+            "lnsgkgsnl/1r5b1/ppppppppp/9/9/9/PPPPPPPPP/1B5R1/LNSGKGSNL b - 1",
+            "lnsgkgsnl/1r5+B1/pppppp1pp/6p2/9/2P6/PP1PPPPPP/7R1/LNSGKGSNL w B 1",
+            "8k/9/9/9/4+p4/9/9/9/4K4 b 2RB2P10p 1",
+            "ln1g5/1r2S1k2/p2pppn2/2ps2p2/1p7/2P6/PPSPPPPLP/2G2K1pr/LN4G1b w BGSLPnp 62"
+        ] {
+            let mut board = shogi.load(sfen);
+            assert_eq!(shogi.rules.save(&mut board), sfen);
+        }
+    }
+
+    #[test]
+    fn promotions_and_captures() {
+        let shogi = Shogi::create::<u128, 14>();
+
+        // A pawn reaching the last rank has to promote, while a silver entering the zone may.
+        let mut board = shogi.load("k8/8P/9/4S4/9/9/9/9/4K4 b - 1");
+        assert_eq!(moves(&mut board, |action| action.starts_with("1b")), vec![ "1b1a+" ]);
+        assert_eq!(moves(&mut board, |action| action.starts_with("5d5c")), vec![ "5d5c", "5d5c+" ]);
+
+        // Capturing a tokin puts a pawn into the hand.
+        let mut board = shogi.load("k8/9/9/9/4+p4/4G4/9/9/4K4 b - 1");
+        board.play_action("5f5e");
+        assert_eq!(shogi.rules.save(&mut board), "k8/9/9/9/4G4/9/9/9/4K4 w P 2");
+        assert_eq!(board.state.pockets[0][PAWN], 1);
+    }
+
+    #[test]
+    fn pawn_drops() {
+        let shogi = Shogi::create::<u128, 14>();
+
+        // Nifu: the 5th file already has a pawn, and pawns can't be dropped on the last rank.
+        let mut board = shogi.load("4k4/9/9/9/9/9/4P4/9/4K4 b P 1");
+        let drops = moves(&mut board, |action| action.starts_with("P*"));
+        assert!(!drops.iter().any(|action| action.starts_with("P*5") || action.ends_with('a')));
+        assert_eq!(drops.len(), 8 * 8);
+
+        // Uchifuzume: the pawn drop on 1b would be mate, since the silver and gold cover every escape.
+        let mut board = shogi.load("8k/6S2/7G1/9/9/9/9/9/4K4 b P 1");
+        assert!(!moves(&mut board, |action| action.starts_with("P*")).contains(&"P*1b".to_string()));
+
+        // Without the silver, the king can escape to 2a, so the drop is only check.
+        let mut board = shogi.load("8k/9/7G1/9/9/9/9/9/4K4 b P 1");
+        assert!(moves(&mut board, |action| action.starts_with("P*")).contains(&"P*1b".to_string()));
+    }
+
+    #[test]
+    fn repetitions() {
+        let shogi = Shogi::create::<u128, 14>();
+
+        // Kings walking back and forth repeat the start for the fourth time after twelve plies, which is a draw.
+        let mut board = shogi.load("4k4/9/9/9/9/9/9/9/4K4 b - 1");
+        for ply in 0..12 {
+            let legals = board.legals();
+            assert!(matches!(board.game_state(&legals), GameState::Ongoing));
+
+            let action = board.find_action([ "5i4i", "5a4a", "4i5i", "4a5a" ][ply % 4]);
+            board.play(action);
+        }
+        let legals = board.legals();
+        assert!(matches!(board.game_state(&legals), GameState::Draw));
+
+        // Taking the last move back takes the repetition back with it.
+        let state = board.positions.last().expect("Positions were kept").clone();
+        board.restore(state);
+        let legals = board.legals();
+        assert!(matches!(board.game_state(&legals), GameState::Ongoing));
+
+        // Checking with every move through the repetition loses.
+        let mut board = shogi.load("8k/9/9/9/8R/9/9/9/4K4 w - 1");
+        for ply in 0..12 {
+            let action = board.find_action([ "1a2a", "1e2e", "2a1a", "2e1e" ][ply % 4]);
+            board.play(action);
+        }
+        let legals = board.legals();
+        assert!(matches!(board.game_state(&legals), GameState::Win(Team::Black)));
+    }
+}